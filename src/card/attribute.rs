@@ -1,9 +1,9 @@
 use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
 
-use crate::localizer::Localizer;
+use crate::{error::VegaError, localizer::Localizer};
 
-#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
 pub enum CardAttribute {
     Slash,
     Strike,
@@ -13,10 +13,16 @@ pub enum CardAttribute {
 }
 
 impl CardAttribute {
+    /// Keys identifying each variant in locale files
+    pub const KEYS: [&'static str; 5] = ["slash", "strike", "ranged", "special", "wisdom"];
+
     pub fn parse(localizer: &Localizer, value: &str) -> Result<CardAttribute> {
         match localizer.match_attribute(value.trim()) {
             Some(key) => Ok(Self::from_str(&key)?),
-            None => bail!("Failed to match attribute `{}`", value),
+            None => bail!(VegaError::Localization {
+                section: "attributes",
+                value: value.to_string(),
+            }),
         }
     }
 
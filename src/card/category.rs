@@ -1,9 +1,9 @@
 use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
 
-use crate::localizer::Localizer;
+use crate::{error::VegaError, localizer::Localizer};
 
-#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
 pub enum CardCategory {
     Leader,
     Character,
@@ -13,10 +13,16 @@ pub enum CardCategory {
 }
 
 impl CardCategory {
+    /// Keys identifying each variant in locale files
+    pub const KEYS: [&'static str; 5] = ["leader", "character", "event", "stage", "don"];
+
     pub fn parse(localizer: &Localizer, value: &str) -> Result<CardCategory> {
         match localizer.match_category(value.trim()) {
             Some(key) => Ok(Self::from_str(&key)?),
-            None => bail!("Failed to match category `{}`", value),
+            None => bail!(VegaError::Localization {
+                section: "categories",
+                value: value.to_string(),
+            }),
         }
     }
 
@@ -1,9 +1,9 @@
 use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
 
-use crate::localizer::Localizer;
+use crate::{error::VegaError, localizer::Localizer};
 
-#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
 pub enum CardColor {
     Red,
     Green,
@@ -14,10 +14,16 @@ pub enum CardColor {
 }
 
 impl CardColor {
+    /// Keys identifying each variant in locale files
+    pub const KEYS: [&'static str; 6] = ["red", "green", "blue", "purple", "black", "yellow"];
+
     pub fn parse(localizer: &Localizer, value: &str) -> Result<CardColor> {
         match localizer.match_color(value.trim()) {
             Some(key) => Ok(Self::from_str(&key)?),
-            None => bail!("Failed to match color `{}`", value),
+            None => bail!(VegaError::Localization {
+                section: "colors",
+                value: value.to_string(),
+            }),
         }
     }
 
@@ -0,0 +1,278 @@
+use anyhow::{bail, Result};
+use log::trace;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::localizer::Localizer;
+
+/// Keyword abilities a card has on its own, e.g. `[Blocker]`.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum CardKeyword {
+    Rush,
+    Blocker,
+    DoubleAttack,
+    Banish,
+}
+
+impl CardKeyword {
+    /// Keys identifying each variant in locale files
+    pub const KEYS: [&'static str; 4] = ["rush", "blocker", "double_attack", "banish"];
+
+    pub fn from_str(value: &str) -> Result<CardKeyword> {
+        match value.to_lowercase().as_str() {
+            "rush" => Ok(Self::Rush),
+            "blocker" => Ok(Self::Blocker),
+            "double_attack" => Ok(Self::DoubleAttack),
+            "banish" => Ok(Self::Banish),
+            _ => bail!("Unsupported keyword `{}`", value),
+        }
+    }
+}
+
+/// When an effect can be activated or resolves, e.g. `[On Play]`.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum CardTiming {
+    OnPlay,
+    WhenAttacking,
+    ActivateMain,
+    Main,
+    Counter,
+    Trigger,
+    OnKo,
+    OnBlock,
+    OnOpponentsAttack,
+    YourTurn,
+    OpponentsTurn,
+    EndOfYourTurn,
+}
+
+impl CardTiming {
+    /// Keys identifying each variant in locale files
+    pub const KEYS: [&'static str; 12] = [
+        "on_play",
+        "when_attacking",
+        "activate_main",
+        "main",
+        "counter",
+        "trigger",
+        "on_ko",
+        "on_block",
+        "on_opponents_attack",
+        "your_turn",
+        "opponents_turn",
+        "end_of_your_turn",
+    ];
+
+    pub fn from_str(value: &str) -> Result<CardTiming> {
+        match value.to_lowercase().as_str() {
+            "on_play" => Ok(Self::OnPlay),
+            "when_attacking" => Ok(Self::WhenAttacking),
+            "activate_main" => Ok(Self::ActivateMain),
+            "main" => Ok(Self::Main),
+            "counter" => Ok(Self::Counter),
+            "trigger" => Ok(Self::Trigger),
+            "on_ko" => Ok(Self::OnKo),
+            "on_block" => Ok(Self::OnBlock),
+            "on_opponents_attack" => Ok(Self::OnOpponentsAttack),
+            "your_turn" => Ok(Self::YourTurn),
+            "opponents_turn" => Ok(Self::OpponentsTurn),
+            "end_of_your_turn" => Ok(Self::EndOfYourTurn),
+            _ => bail!("Unsupported timing `{}`", value),
+        }
+    }
+}
+
+/// Structured view of the bracketed markers found in a card effect.
+///
+/// Only markers opening a clause are taken into account, so `[Blocker]` in "your opponent
+/// cannot activate [Blocker]" or a card name such as `[Nami]` are left out.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct CardAbilities {
+    #[serde(default)]
+    pub keywords: Vec<CardKeyword>,
+    #[serde(default)]
+    pub timings: Vec<CardTiming>,
+    /// Number of attached DON!! cards required, one entry per `[DON!! xN]`
+    #[serde(default)]
+    pub don_requirements: Vec<u32>,
+    #[serde(default)]
+    pub once_per_turn: bool,
+}
+
+impl CardAbilities {
+    pub fn parse(localizer: &Localizer, effect: &str, trigger: Option<&str>) -> Result<Self> {
+        let marker_reg = Regex::new(r"[\[【]([^\]】]+)[\]】]")?;
+        let mut abilities = CardAbilities::default();
+
+        let mut clause_start = 0;
+        for capture in marker_reg.captures_iter(effect) {
+            let marker = capture.get(0).unwrap();
+            let preceding = effect[clause_start..marker.start()].trim();
+            clause_start = marker.end();
+
+            if !Self::is_clause_boundary(preceding) {
+                trace!("skipping inline marker: {}", marker.as_str());
+                continue;
+            }
+
+            let value = capture[1].trim();
+            if let Some(count) = Self::parse_don_requirement(localizer, value) {
+                abilities.don_requirements.push(count);
+                continue;
+            }
+
+            match localizer.match_effect(value).as_deref() {
+                Some("once_per_turn") => abilities.once_per_turn = true,
+                Some(key) => {
+                    if let Ok(keyword) = CardKeyword::from_str(key) {
+                        Self::push_unique(&mut abilities.keywords, keyword);
+                    } else {
+                        Self::push_unique(&mut abilities.timings, CardTiming::from_str(key)?);
+                    }
+                }
+                None => trace!("unknown effect marker: {}", value),
+            }
+        }
+
+        if trigger.is_some() {
+            Self::push_unique(&mut abilities.timings, CardTiming::Trigger);
+        }
+
+        Ok(abilities)
+    }
+
+    /// A marker opens a clause at the start of the text, after another marker, after a
+    /// reminder text in parentheses, at the end of a sentence or after a line break.
+    fn is_clause_boundary(preceding: &str) -> bool {
+        preceding.is_empty()
+            || preceding.ends_with(')')
+            || preceding.ends_with('）')
+            || preceding.ends_with('.')
+            || preceding.ends_with('。')
+            || preceding.ends_with('>')
+    }
+
+    fn parse_don_requirement(localizer: &Localizer, value: &str) -> Option<u32> {
+        let prefixes = localizer.effects.get("don")?;
+        prefixes.iter().find_map(|prefix| {
+            let count = value.strip_prefix(prefix)?.trim();
+            count.parse::<u32>().ok()
+        })
+    }
+
+    fn push_unique<T: PartialEq>(values: &mut Vec<T>, value: T) {
+        if !values.contains(&value) {
+            values.push(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::LanguageCode;
+    use std::path::Path;
+
+    fn get_test_localizer(language: LanguageCode) -> Localizer {
+        Localizer::load(Path::new("config"), language).unwrap()
+    }
+
+    fn parse(effect: &str) -> CardAbilities {
+        let localizer = get_test_localizer(LanguageCode::English);
+        CardAbilities::parse(&localizer, effect, None).unwrap()
+    }
+
+    #[test]
+    fn from_str_double_attack_returns_ok() {
+        assert_eq!(
+            CardKeyword::from_str("DOUBLE_attack").unwrap(),
+            CardKeyword::DoubleAttack
+        );
+    }
+
+    #[test]
+    fn from_str_on_ko_returns_ok() {
+        assert_eq!(CardTiming::from_str("on_KO").unwrap(), CardTiming::OnKo);
+    }
+
+    #[test]
+    fn parse_don_requirement_and_timing() {
+        let abilities = parse("[DON!! x1] [Your Turn] All of your Characters gain +1000 power.");
+
+        assert_eq!(abilities.don_requirements, vec![1]);
+        assert_eq!(abilities.timings, vec![CardTiming::YourTurn]);
+        assert!(abilities.keywords.is_empty());
+        assert!(!abilities.once_per_turn);
+    }
+
+    #[test]
+    fn parse_skips_inline_markers() {
+        let abilities = parse(
+            "[Rush] (This card can attack on the turn in which it is played.) [DON!! x2] \
+             [When Attacking] Your opponent cannot activate [Blocker] during this battle.",
+        );
+
+        assert_eq!(abilities.keywords, vec![CardKeyword::Rush]);
+        assert_eq!(abilities.timings, vec![CardTiming::WhenAttacking]);
+        assert_eq!(abilities.don_requirements, vec![2]);
+    }
+
+    #[test]
+    fn parse_skips_card_names() {
+        let abilities =
+            parse("[On Play] Reveal up to 1 card other than [Nami] and add it to your hand.");
+
+        assert_eq!(abilities.timings, vec![CardTiming::OnPlay]);
+        assert!(abilities.keywords.is_empty());
+    }
+
+    #[test]
+    fn parse_once_per_turn() {
+        let abilities = parse("[Activate: Main] [Once Per Turn] Give up to 1 rested DON!! card.");
+
+        assert_eq!(abilities.timings, vec![CardTiming::ActivateMain]);
+        assert!(abilities.once_per_turn);
+    }
+
+    #[test]
+    fn parse_marker_after_sentence() {
+        let abilities = parse("[Blocker] [On K.O.] Draw 1 card. [End of Your Turn] Trash 1 card.");
+
+        assert_eq!(abilities.keywords, vec![CardKeyword::Blocker]);
+        assert_eq!(
+            abilities.timings,
+            vec![CardTiming::OnKo, CardTiming::EndOfYourTurn]
+        );
+    }
+
+    #[test]
+    fn parse_trigger_adds_timing() {
+        let localizer = get_test_localizer(LanguageCode::English);
+        let abilities = CardAbilities::parse(
+            &localizer,
+            "[Counter] Up to 1 of your Leader gains +2000 power during this battle.",
+            Some("Draw 1 card."),
+        )
+        .unwrap();
+
+        assert_eq!(
+            abilities.timings,
+            vec![CardTiming::Counter, CardTiming::Trigger]
+        );
+    }
+
+    #[test]
+    fn parse_japanese_markers() {
+        let localizer = get_test_localizer(LanguageCode::Japanese);
+        let abilities = CardAbilities::parse(
+            &localizer,
+            "【ドン!!×1】【アタック時】【ターン1回】カード1枚を引く。",
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(abilities.don_requirements, vec![1]);
+        assert_eq!(abilities.timings, vec![CardTiming::WhenAttacking]);
+        assert!(abilities.once_per_turn);
+    }
+}
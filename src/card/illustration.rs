@@ -0,0 +1,79 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{error::VegaError, localizer::Localizer};
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum CardIllustration {
+    Comic,
+    Animation,
+    Original,
+    Other,
+}
+
+impl CardIllustration {
+    /// Keys identifying each variant in locale files
+    pub const KEYS: [&'static str; 4] = ["comic", "animation", "original", "other"];
+
+    pub fn parse(localizer: &Localizer, value: &str) -> Result<CardIllustration> {
+        match localizer.match_illustration(value.trim()) {
+            Some(key) => Ok(Self::from_str(&key)?),
+            None => bail!(VegaError::Localization {
+                section: "illustrations",
+                value: value.to_string(),
+            }),
+        }
+    }
+
+    pub fn from_str(value: &str) -> Result<CardIllustration> {
+        match value.to_lowercase().as_str() {
+            "comic" => Ok(Self::Comic),
+            "animation" => Ok(Self::Animation),
+            "original" => Ok(Self::Original),
+            "other" => Ok(Self::Other),
+            _ => bail!("Unsupported illustration `{}`", value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_str_comic_returns_ok() {
+        assert_eq!(
+            CardIllustration::from_str("COMic").unwrap(),
+            CardIllustration::Comic
+        );
+    }
+
+    #[test]
+    fn from_str_animation_returns_ok() {
+        assert_eq!(
+            CardIllustration::from_str("animATION").unwrap(),
+            CardIllustration::Animation
+        );
+    }
+
+    #[test]
+    fn from_str_original_returns_ok() {
+        assert_eq!(
+            CardIllustration::from_str("Original").unwrap(),
+            CardIllustration::Original
+        );
+    }
+
+    #[test]
+    fn from_str_other_returns_ok() {
+        assert_eq!(
+            CardIllustration::from_str("other").unwrap(),
+            CardIllustration::Other
+        );
+    }
+
+    #[test]
+    fn from_str_invalid_returns_err() {
+        assert!(CardIllustration::from_str("manga").is_err());
+    }
+}
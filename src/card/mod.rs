@@ -1,13 +1,21 @@
 pub mod attribute;
 pub mod category;
 pub mod color;
+pub mod effect;
+pub mod illustration;
 pub mod model;
 pub mod rarity;
 pub mod scraper;
+#[cfg(test)]
+pub mod test_util;
+pub mod variant;
 
 pub use self::attribute::CardAttribute;
 pub use self::category::CardCategory;
 pub use self::color::CardColor;
+pub use self::effect::CardAbilities;
+pub use self::illustration::CardIllustration;
 pub use self::model::Card;
 pub use self::rarity::CardRarity;
 pub use self::scraper::CardScraper;
+pub use self::variant::CardVariant;
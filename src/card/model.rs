@@ -1,25 +1,36 @@
 use std::fmt;
 
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
-use super::{CardAttribute, CardCategory, CardColor, CardRarity};
+use super::{
+    CardAbilities, CardAttribute, CardCategory, CardColor, CardIllustration, CardRarity,
+    CardVariant,
+};
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Card {
     pub id: String,
+    /// Id shared by every printing of the same card, e.g. `OP01-001` for `OP01-001_p1`
+    #[serde(default)]
+    pub base_id: String,
+    /// Unset for the base print
+    #[serde(default)]
+    pub variant: Option<CardVariant>,
     pub pack_id: String,
     pub name: String,
     pub rarity: CardRarity,
     pub category: CardCategory,
     // pub number: i32,
-    // #[serde(skip_serializing)]
-    // pub copyright: String,
+    // No copyright: the cardlist only shows the site-wide notice in its footer, never one per card
+    pub block: Option<i32>,      // Block icon used by the rotation rules
+    pub product: Option<String>, // Products listed under "Card Set(s)"
 
     // Images
     pub img_url: String,
     pub img_full_url: Option<String>,
-    // pub illustration: CardIllustration,
-    // pub illustrator_name: String,
+    pub illustration: Option<CardIllustration>,
+    pub illustrator_name: Option<String>,
 
     // Gameplay
     pub colors: Vec<CardColor>,
@@ -31,19 +42,53 @@ pub struct Card {
     pub types: Vec<String>,
     pub effect: String,
     pub trigger: Option<String>,
+    #[serde(flatten)]
+    pub abilities: CardAbilities,
     // pub notes: String,
 }
 
+impl Card {
+    /// Fills the base id and variant of cards pulled before variants were tracked.
+    pub fn normalize(&mut self) -> Result<()> {
+        if self.base_id.is_empty() {
+            let (base_id, variant) = CardVariant::parse_id(&self.id)?;
+            self.base_id = base_id;
+            self.variant = variant;
+        }
+
+        Ok(())
+    }
+}
+
 impl fmt::Display for Card {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}. `{}`", self.id, self.name)
     }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-pub enum CardIllustration {
-    Comic,
-    Animation,
-    Original,
-    Other,
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::{test_util, variant::CardVariantKind};
+
+    #[test]
+    fn normalize_fills_missing_base_id() {
+        let mut card = Card {
+            base_id: String::new(),
+            ..test_util::card("OP01-001_p1")
+        };
+        card.normalize().unwrap();
+
+        assert_eq!(card.base_id, "OP01-001");
+        assert_eq!(card.variant.unwrap().kind, CardVariantKind::Parallel);
+    }
+
+    #[test]
+    fn normalize_keeps_existing_base_id() {
+        let mut card = test_util::card("OP01-001");
+        card.normalize().unwrap();
+
+        assert_eq!(card.base_id, "OP01-001");
+        assert_eq!(card.variant, None);
+    }
 }
@@ -1,9 +1,9 @@
 use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
 
-use crate::localizer::Localizer;
+use crate::{error::VegaError, localizer::Localizer};
 
-#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
 pub enum CardRarity {
     Common = 0,
     Uncommon = 1,
@@ -17,10 +17,26 @@ pub enum CardRarity {
 }
 
 impl CardRarity {
+    /// Keys identifying each variant in locale files
+    pub const KEYS: [&'static str; 9] = [
+        "common",
+        "uncommon",
+        "rare",
+        "super_rare",
+        "secret_rare",
+        "leader",
+        "special",
+        "treasure_rare",
+        "promo",
+    ];
+
     pub fn parse(localizer: &Localizer, value: &str) -> Result<CardRarity> {
         match localizer.match_rarity(value) {
             Some(key) => Ok(Self::from_str(&key)?),
-            None => bail!("Failed to match rarity `{}`", value),
+            None => bail!(VegaError::Localization {
+                section: "rarities",
+                value: value.to_string(),
+            }),
         }
     }
 
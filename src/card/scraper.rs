@@ -1,10 +1,14 @@
-use anyhow::{bail, Context, Result};
-use log::{debug, trace};
+use anyhow::{bail, Result};
+use log::{debug, trace, warn};
 use regex::Regex;
 use scraper::{ElementRef, Html};
 
 use crate::{
-    card::{Card, CardAttribute, CardCategory, CardColor, CardRarity},
+    card::{
+        Card, CardAbilities, CardAttribute, CardCategory, CardColor, CardIllustration, CardRarity,
+        CardVariant,
+    },
+    error::VegaError,
     localizer::Localizer,
 };
 
@@ -18,33 +22,45 @@ impl CardScraper {
         pack_id: &str,
     ) -> Result<Card> {
         trace!("start create card: `{}`", card_id);
-        let dl_elem = Self::get_dl_node(document, card_id.to_string())?;
+        let dl_elem = Self::get_dl_node(localizer, document, card_id)?;
 
         let id = Self::fetch_id(dl_elem)?;
+        let (base_id, variant) = CardVariant::parse_id(&id)?;
         let pack_id = pack_id.to_string();
-        let name = Self::fetch_name(dl_elem)?;
+        let name = Self::fetch_name(localizer, dl_elem)?;
         let rarity = Self::fetch_rarity(localizer, dl_elem)?;
         let category = Self::fetch_category(localizer, dl_elem)?;
-        let img_url = Self::fetch_img_url(dl_elem)?;
+        let img_url = Self::fetch_img_url(localizer, dl_elem)?;
         let img_full_url = None;
+        let illustration = Self::fetch_illustration(localizer, dl_elem)?;
+        let illustrator_name = Self::fetch_illustrator_name(localizer, dl_elem)?;
+        let block = Self::fetch_block(localizer, dl_elem)?;
+        let product = Self::fetch_product(localizer, dl_elem)?;
 
         let colors = Self::fetch_colors(localizer, dl_elem)?;
-        let cost = Self::fetch_cost(dl_elem)?;
+        let cost = Self::fetch_cost(localizer, dl_elem)?;
         let attributes = Self::fetch_attributes(localizer, dl_elem)?;
-        let power = Self::fetch_power(dl_elem)?;
-        let counter = Self::fetch_counter(dl_elem)?;
-        let types = Self::fetch_types(dl_elem)?;
-        let effect = Self::fetch_effect(dl_elem)?;
-        let trigger = Self::fetch_trigger(dl_elem)?;
+        let power = Self::fetch_power(localizer, dl_elem)?;
+        let counter = Self::fetch_counter(localizer, dl_elem)?;
+        let types = Self::fetch_types(localizer, dl_elem)?;
+        let effect = Self::fetch_effect(localizer, dl_elem)?;
+        let trigger = Self::fetch_trigger(localizer, dl_elem)?;
+        let abilities = CardAbilities::parse(localizer, &effect, trigger.as_deref())?;
 
         let card = Card {
             id,
+            base_id,
+            variant,
             pack_id,
             name,
             rarity,
             category,
+            block,
+            product,
             img_url,
             img_full_url,
+            illustration,
+            illustrator_name,
             colors,
             cost,
             attributes,
@@ -53,6 +69,7 @@ impl CardScraper {
             types,
             effect,
             trigger,
+            abilities,
         };
 
         debug!("processed card: `{}`", card);
@@ -64,15 +81,15 @@ impl CardScraper {
         trace!("fetching card.id...");
         let id = element
             .attr("id")
-            .context("expected to find id attr on <dl>")?
+            .ok_or_else(|| VegaError::selector_missing_attr("dl", "id"))?
             .to_string();
 
         trace!("fetched card.id: {}", id);
         Ok(id)
     }
 
-    pub fn fetch_name(element: ElementRef) -> Result<String> {
-        let sel = "dt>div.cardName";
+    pub fn fetch_name(localizer: &Localizer, element: ElementRef) -> Result<String> {
+        let sel = &localizer.selectors.name;
         trace!("fetching card.name ({})...", sel);
 
         let name = Self::get_child_node(element, sel.to_string())?.inner_html();
@@ -82,7 +99,7 @@ impl CardScraper {
     }
 
     pub fn fetch_rarity(localizer: &Localizer, element: ElementRef) -> Result<CardRarity> {
-        let sel = "dt>div.infoCol>span:nth-child(2)";
+        let sel = &localizer.selectors.rarity;
         trace!("fetching card.rarity ({})...", sel);
 
         let raw_rarity = Self::get_child_node(element, sel.to_string())?.inner_html();
@@ -95,7 +112,7 @@ impl CardScraper {
     }
 
     pub fn fetch_category(localizer: &Localizer, element: ElementRef) -> Result<CardCategory> {
-        let sel = "dt>div.infoCol>span:nth-child(3)";
+        let sel = &localizer.selectors.category;
         trace!("fetching card.category ({})...", sel);
 
         let raw_category = Self::get_child_node(element, sel.to_string())?.inner_html();
@@ -107,14 +124,14 @@ impl CardScraper {
         Ok(category)
     }
 
-    pub fn fetch_img_url(element: ElementRef) -> Result<String> {
-        let sel = "dd>div.frontCol>img";
+    pub fn fetch_img_url(localizer: &Localizer, element: ElementRef) -> Result<String> {
+        let sel = &localizer.selectors.img_url;
         trace!("fetching card.img_url ({})...", sel);
 
         let img_elem = Self::get_child_node(element, sel.to_string())?;
         let img_url = img_elem
             .attr("data-src")
-            .context("no data-src attr")?
+            .ok_or_else(|| VegaError::selector_missing_attr(sel, "data-src"))?
             .to_string();
 
         trace!("fetched card.img_url: {}", img_url);
@@ -122,7 +139,7 @@ impl CardScraper {
     }
 
     pub fn fetch_colors(localizer: &Localizer, element: ElementRef) -> Result<Vec<CardColor>> {
-        let sel = "dd>div.backCol div.color";
+        let sel = &localizer.selectors.colors;
         trace!("fetching card.colors ({})...", sel);
 
         let raw_colors = Self::get_child_node(element, sel.to_string())?.inner_html();
@@ -142,8 +159,8 @@ impl CardScraper {
         Ok(colors)
     }
 
-    pub fn fetch_cost(element: ElementRef) -> Result<Option<i32>> {
-        let sel = "dd>div.backCol>div.col2>div.cost";
+    pub fn fetch_cost(localizer: &Localizer, element: ElementRef) -> Result<Option<i32>> {
+        let sel = &localizer.selectors.cost;
         trace!("fetching card.cost ({})...", sel);
 
         let raw_cost = Self::get_child_node(element, sel.to_string())?.inner_html();
@@ -160,7 +177,11 @@ impl CardScraper {
                 trace!("processed card.cost");
                 Ok(Some(val))
             }
-            Err(e) => bail!("failed to parse card.cost value `{}`: {}", raw_cost, e),
+            Err(e) => bail!(VegaError::Parse {
+                field: String::from("card.cost"),
+                value: raw_cost,
+                reason: e.to_string(),
+            }),
         }
     }
 
@@ -168,11 +189,14 @@ impl CardScraper {
         localizer: &Localizer,
         element: ElementRef,
     ) -> Result<Vec<CardAttribute>> {
-        let sel = "dd>div.backCol>div.col2>div.attribute>img";
+        let sel = &localizer.selectors.attributes;
         trace!("fetching card.attributes ({})...", sel);
 
         if let Ok(attr_img) = Self::get_child_node(element, sel.to_string()) {
-            let raw_attributes = attr_img.attr("alt").context("no alt attr")?.to_string();
+            let raw_attributes = attr_img
+                .attr("alt")
+                .ok_or_else(|| VegaError::selector_missing_attr(sel, "alt"))?
+                .to_string();
             trace!("fetched card.attributes: {}", raw_attributes);
 
             if raw_attributes.is_empty() {
@@ -197,8 +221,8 @@ impl CardScraper {
         Ok(Vec::new())
     }
 
-    pub fn fetch_power(element: ElementRef) -> Result<Option<i32>> {
-        let sel = "dd>div.backCol>div.col2>div.power";
+    pub fn fetch_power(localizer: &Localizer, element: ElementRef) -> Result<Option<i32>> {
+        let sel = &localizer.selectors.power;
         trace!("fetching card.power ({})...", sel);
 
         let raw_power = Self::get_child_node(element, sel.to_string())?.inner_html();
@@ -215,12 +239,16 @@ impl CardScraper {
                 trace!("processed card.power");
                 Ok(Some(val))
             }
-            Err(e) => bail!("failed to parse card.power value `{}`: {}", raw_power, e),
+            Err(e) => bail!(VegaError::Parse {
+                field: String::from("card.power"),
+                value: raw_power,
+                reason: e.to_string(),
+            }),
         }
     }
 
-    pub fn fetch_counter(element: ElementRef) -> Result<Option<i32>> {
-        let sel = "dd>div.backCol>div.col2>div.counter";
+    pub fn fetch_counter(localizer: &Localizer, element: ElementRef) -> Result<Option<i32>> {
+        let sel = &localizer.selectors.counter;
         trace!("fetching card.counter ({})...", sel);
 
         let raw_counter = Self::get_child_node(element, sel.to_string())?.inner_html();
@@ -237,16 +265,16 @@ impl CardScraper {
                 trace!("processed card.counter");
                 Ok(Some(val))
             }
-            Err(e) => bail!(
-                "failed to parse card.counter value `{}`: {}",
-                raw_counter,
-                e
-            ),
+            Err(e) => bail!(VegaError::Parse {
+                field: String::from("card.counter"),
+                value: raw_counter,
+                reason: e.to_string(),
+            }),
         }
     }
 
-    pub fn fetch_types(element: ElementRef) -> Result<Vec<String>> {
-        let sel = "dd>div.backCol>div.feature";
+    pub fn fetch_types(localizer: &Localizer, element: ElementRef) -> Result<Vec<String>> {
+        let sel = &localizer.selectors.types;
         trace!("fetching card.types ({})...", sel);
 
         let types = Self::get_child_node(element, sel.to_string())?.inner_html();
@@ -259,8 +287,8 @@ impl CardScraper {
         Ok(types)
     }
 
-    pub fn fetch_effect(element: ElementRef) -> Result<String> {
-        let sel = "dd>div.backCol>div.text";
+    pub fn fetch_effect(localizer: &Localizer, element: ElementRef) -> Result<String> {
+        let sel = &localizer.selectors.effect;
         trace!("fetching card.effect ({})...", sel);
 
         let effect = Self::get_child_node(element, sel.to_string())?.inner_html();
@@ -270,8 +298,8 @@ impl CardScraper {
         Ok(effect)
     }
 
-    pub fn fetch_trigger(element: ElementRef) -> Result<Option<String>> {
-        let sel = "dd>div.backCol>div.trigger";
+    pub fn fetch_trigger(localizer: &Localizer, element: ElementRef) -> Result<Option<String>> {
+        let sel = &localizer.selectors.trigger;
         trace!("fetching card.trigger ({})...", sel);
 
         if let Ok(trigger_div) = Self::get_child_node(element, sel.to_string()) {
@@ -286,6 +314,99 @@ impl CardScraper {
         Ok(None)
     }
 
+    pub fn fetch_illustration(
+        localizer: &Localizer,
+        element: ElementRef,
+    ) -> Result<Option<CardIllustration>> {
+        let sel = &localizer.selectors.illustration;
+        trace!("fetching card.illustration ({})...", sel);
+
+        let Some(raw_illustration) = Self::get_optional_text(element, sel)? else {
+            trace!("card.illustration no html found");
+            return Ok(None);
+        };
+
+        trace!("fetched card.illustration: {}", raw_illustration);
+        // A new label is not worth dropping the card, it is kept under `Other` until the locale
+        // lists it
+        let illustration = match CardIllustration::parse(localizer, &raw_illustration) {
+            Ok(illustration) => illustration,
+            Err(e) => {
+                warn!("{:#}, using `Other`", e);
+                CardIllustration::Other
+            }
+        };
+
+        trace!("processed card.illustration");
+        Ok(Some(illustration))
+    }
+
+    pub fn fetch_illustrator_name(
+        localizer: &Localizer,
+        element: ElementRef,
+    ) -> Result<Option<String>> {
+        let sel = &localizer.selectors.illustrator_name;
+        trace!("fetching card.illustrator_name ({})...", sel);
+
+        let illustrator_name = Self::get_optional_text(element, sel)?;
+        trace!("fetched card.illustrator_name: {:?}", illustrator_name);
+
+        Ok(illustrator_name)
+    }
+
+    pub fn fetch_block(localizer: &Localizer, element: ElementRef) -> Result<Option<i32>> {
+        let sel = &localizer.selectors.block;
+        trace!("fetching card.block ({})...", sel);
+
+        let Some(raw_block) = Self::get_optional_text(element, sel)? else {
+            trace!("card.block no html found");
+            return Ok(None);
+        };
+
+        trace!("fetched card.block: {}", raw_block);
+        if raw_block == "-" {
+            trace!("card.block unset");
+            return Ok(None);
+        }
+
+        match raw_block.parse::<i32>() {
+            Ok(val) => {
+                trace!("processed card.block");
+                Ok(Some(val))
+            }
+            Err(e) => bail!(VegaError::Parse {
+                field: String::from("card.block"),
+                value: raw_block,
+                reason: e.to_string(),
+            }),
+        }
+    }
+
+    pub fn fetch_product(localizer: &Localizer, element: ElementRef) -> Result<Option<String>> {
+        let sel = &localizer.selectors.product;
+        trace!("fetching card.product ({})...", sel);
+
+        let product = Self::get_optional_text(element, sel)?;
+        trace!("fetched card.product: {:?}", product);
+
+        Ok(product)
+    }
+
+    /// Text of an element that is not shown for every card or locale, `None` when missing or
+    /// empty.
+    fn get_optional_text(element: ElementRef, selector: &str) -> Result<Option<String>> {
+        let Some(node) = Self::get_optional_child_node(element, selector)? else {
+            return Ok(None);
+        };
+
+        let text = Self::strip_html_tags(&node.inner_html())?;
+        if text.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(text))
+    }
+
     fn strip_html_tags(value: &str) -> Result<String> {
         let reg = Regex::new(r"<[^>]*>.*?</[^>]*>")?;
         let result = reg.replace_all(value, "").trim().to_string();
@@ -293,20 +414,44 @@ impl CardScraper {
     }
 
     fn get_child_node(element: ElementRef, selector: String) -> Result<ElementRef> {
-        let node_sel = scraper::Selector::parse(&selector).unwrap();
+        match Self::get_optional_child_node(element, &selector)? {
+            Some(node) => Ok(node),
+            None => bail!(VegaError::selector_missing(&selector)),
+        }
+    }
+
+    /// Only node matching `selector`, `None` when there is none.
+    fn get_optional_child_node<'a>(
+        element: ElementRef<'a>,
+        selector: &str,
+    ) -> Result<Option<ElementRef<'a>>> {
+        let node_sel = scraper::Selector::parse(selector).unwrap();
         let results: Vec<_> = element.select(&node_sel).collect();
 
         match results.len() {
-            0 => bail!("Expected `{}` but got nothing", selector),
-            1 => Ok(*results.first().unwrap()),
-            _ => bail!("Expected single `{}` but got many", selector),
+            0 => Ok(None),
+            1 => Ok(Some(results[0])),
+            count => bail!(VegaError::Selector {
+                selector: selector.to_string(),
+                problem: format!("matched {} elements instead of one", count),
+            }),
         }
     }
 
-    pub fn get_dl_node(document: &Html, card_id: String) -> Result<ElementRef> {
-        let dl_sel = format!("dl#{}", card_id);
-        let dl_sel = scraper::Selector::parse(&dl_sel).unwrap();
-        let dl_elem = document.select(&dl_sel).next().unwrap();
+    pub fn get_dl_node<'a>(
+        localizer: &Localizer,
+        document: &'a Html,
+        card_id: &str,
+    ) -> Result<ElementRef<'a>> {
+        let dl_sel = localizer.selectors.card_node(card_id);
+        let dl_selector = scraper::Selector::parse(&dl_sel).map_err(|e| VegaError::Selector {
+            selector: dl_sel.clone(),
+            problem: e.to_string(),
+        })?;
+        let dl_elem = document
+            .select(&dl_selector)
+            .next()
+            .ok_or_else(|| VegaError::selector_missing(&dl_sel))?;
 
         Ok(dl_elem)
     }
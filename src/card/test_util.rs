@@ -0,0 +1,29 @@
+use super::{Card, CardAbilities, CardAttribute, CardCategory, CardColor, CardRarity};
+
+/// Plain Character card for tests, which override only the fields they look at.
+pub fn card(id: &str) -> Card {
+    Card {
+        id: id.to_string(),
+        base_id: id.to_string(),
+        variant: None,
+        pack_id: String::from("569101"),
+        name: String::from("Nami"),
+        rarity: CardRarity::Rare,
+        category: CardCategory::Character,
+        img_url: format!("../images/cardlist/card/{}.png", id),
+        img_full_url: None,
+        illustration: None,
+        illustrator_name: None,
+        block: None,
+        product: None,
+        colors: vec![CardColor::Red],
+        cost: Some(1),
+        attributes: vec![CardAttribute::Special],
+        power: Some(2000),
+        counter: Some(1000),
+        types: vec![String::from("Straw Hat Crew")],
+        effect: String::from("[On Play] Look at 5 cards from the top of your deck."),
+        trigger: None,
+        abilities: CardAbilities::default(),
+    }
+}
@@ -0,0 +1,106 @@
+use anyhow::{bail, Result};
+use log::warn;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum CardVariantKind {
+    /// Alternate art printing, ids ending with `_pN`
+    Parallel,
+    /// Reprint in a later product, ids ending with `_rN`
+    Reprint,
+    /// Suffix not known yet, kept so the card is still listed
+    Other,
+}
+
+/// Printing of a card other than its base print, e.g. `OP01-001_p1`.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct CardVariant {
+    pub kind: CardVariantKind,
+    /// Number following the suffix letter, starting at 1
+    pub index: u32,
+}
+
+impl CardVariantKind {
+    pub fn from_str(value: &str) -> Result<CardVariantKind> {
+        match value.to_lowercase().as_str() {
+            "p" => Ok(Self::Parallel),
+            "r" => Ok(Self::Reprint),
+            _ => bail!("Unsupported variant kind `{}`", value),
+        }
+    }
+}
+
+impl CardVariant {
+    /// Splits a card id into the base card id and the variant it denotes, if any.
+    pub fn parse_id(card_id: &str) -> Result<(String, Option<CardVariant>)> {
+        static VARIANT_ID_REG: OnceLock<Regex> = OnceLock::new();
+        let reg = VARIANT_ID_REG.get_or_init(|| Regex::new(r"^(.+)_([A-Za-z]+)(\d+)$").unwrap());
+        let Some(captures) = reg.captures(card_id) else {
+            return Ok((card_id.to_string(), None));
+        };
+
+        let kind = match CardVariantKind::from_str(&captures[2]) {
+            Ok(kind) => kind,
+            Err(e) => {
+                warn!("{} in card id `{}`", e, card_id);
+                CardVariantKind::Other
+            }
+        };
+
+        let variant = CardVariant {
+            kind,
+            index: captures[3].parse()?,
+        };
+
+        Ok((captures[1].to_string(), Some(variant)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_id_base_print_returns_no_variant() {
+        let (base_id, variant) = CardVariant::parse_id("OP01-001").unwrap();
+        assert_eq!(base_id, "OP01-001");
+        assert_eq!(variant, None);
+    }
+
+    #[test]
+    fn parse_id_parallel_returns_variant() {
+        let (base_id, variant) = CardVariant::parse_id("OP01-001_p2").unwrap();
+        assert_eq!(base_id, "OP01-001");
+        assert_eq!(
+            variant,
+            Some(CardVariant {
+                kind: CardVariantKind::Parallel,
+                index: 2
+            })
+        );
+    }
+
+    #[test]
+    fn parse_id_reprint_returns_variant() {
+        let (base_id, variant) = CardVariant::parse_id("ST01-012_r1").unwrap();
+        assert_eq!(base_id, "ST01-012");
+        assert_eq!(variant.unwrap().kind, CardVariantKind::Reprint);
+    }
+
+    #[test]
+    fn parse_id_unknown_suffix_returns_other() {
+        let (base_id, variant) = CardVariant::parse_id("P-001_x1").unwrap();
+        assert_eq!(base_id, "P-001");
+        assert_eq!(variant.unwrap().kind, CardVariantKind::Other);
+    }
+
+    #[test]
+    fn from_str_parallel_returns_ok() {
+        assert_eq!(
+            CardVariantKind::from_str("P").unwrap(),
+            CardVariantKind::Parallel
+        );
+    }
+}
@@ -1,7 +1,9 @@
 use std::{ffi::OsString, path::PathBuf, str::FromStr};
 
 use anyhow::Result;
-use clap::{command, Parser, Subcommand, ValueEnum};
+use clap::{builder::RangedU64ValueParser, Parser, Subcommand, ValueEnum};
+
+use crate::{output::OutputFormat, query::CardFilter};
 
 #[derive(Debug, Parser)]
 #[command(name = "veganet")]
@@ -17,10 +19,42 @@ pub struct Cli {
     #[arg(short, long, alias = "lang", value_name = "LANGUAGE", default_value_t = LanguageCode::English, value_enum)]
     pub language: LanguageCode,
 
-    /// Specify path to the config directory (where locales are stored)
+    /// Directory of locale files overriding the built-in ones (defaults to the user config dir)
     #[arg(short = 'c', long = "config-dir")]
     pub config_directory_path: Option<PathBuf>,
 
+    /// Format used to print packs and cards (JSON by default, text for `diff`)
+    #[arg(short, long, value_name = "FORMAT", value_enum)]
+    pub format: Option<OutputFormat>,
+
+    /// List alternate prints of a card (e.g. `OP01-001_p1`) under their base card
+    #[arg(long = "group-variants")]
+    pub group_variants: bool,
+
+    /// Skip cards that cannot be scraped instead of failing the whole pack, and list them at the end
+    #[arg(long)]
+    pub lenient: bool,
+
+    /// Serve requests from a directory of saved pages instead of the live site
+    #[arg(long = "fixtures-dir", value_name = "FIXTURES_DIR")]
+    pub fixtures_dir: Option<PathBuf>,
+
+    /// Minimum delay between two requests to the site, in milliseconds
+    #[arg(long = "min-delay", value_name = "MS")]
+    pub min_delay_ms: Option<u64>,
+
+    /// Maximum number of attempts per request before giving up
+    #[arg(long = "max-attempts", value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_attempts: Option<u32>,
+
+    /// Delay before the first retry (doubled on every attempt), in milliseconds
+    #[arg(long = "retry-backoff", value_name = "MS")]
+    pub retry_backoff_ms: Option<u64>,
+
+    /// Longest delay between two attempts computed by the backoff, in milliseconds
+    #[arg(long = "max-backoff", value_name = "MS")]
+    pub max_backoff_ms: Option<u64>,
+
     #[command(flatten)]
     pub verbose: clap_verbosity_flag::Verbosity,
 }
@@ -29,7 +63,14 @@ pub struct Cli {
 pub enum Commands {
     /// Get the list of all existing packs
     #[command(alias = "pack", alias = "pak")]
-    Packs,
+    Packs {
+        /// Sort packs by the release date found in the products page instead of the site order
+        #[arg(long = "by-release")]
+        by_release: bool,
+    },
+    /// Get the release date and product info of every pack
+    #[command(alias = "product")]
+    Products,
     /// Get all cards within the given pack
     #[command(alias = "card", alias = "car")]
     Cards {
@@ -44,15 +85,101 @@ pub enum Commands {
         /// Directory where the images should be saved
         #[arg(short, long = "output-dir")]
         output_dir: PathBuf,
+        /// Reuse an existing output directory and skip images that are already there
+        #[arg(short, long)]
+        sync: bool,
+        /// Maximum number of images to download in parallel
+        #[arg(short, long, default_value_t = 1, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+        jobs: usize,
+    },
+    /// Pull packs, cards and images for every pack without prompting
+    #[command(name = "pull-all", alias = "pull")]
+    PullAll {
+        /// Root directory where the data should be saved
+        #[arg(short, long = "output-dir", default_value = "./data")]
+        output_dir: PathBuf,
+        /// Languages to pull data for (defaults to the global language)
+        #[arg(long = "languages", alias = "langs", value_delimiter = ',', value_enum)]
+        languages: Vec<LanguageCode>,
+        /// Download card images as well
+        #[arg(short = 'i', long = "with-images")]
+        with_images: bool,
+        /// Only fetch packs and images that are missing or changed, resuming interrupted runs
+        #[arg(short, long)]
+        sync: bool,
+        /// Maximum number of images to download in parallel
+        #[arg(short, long, default_value_t = 1, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+        jobs: usize,
+    },
+    /// Search previously pulled cards without network access
+    #[command(alias = "search")]
+    Query {
+        /// Root directory of the pulled data
+        #[arg(short, long = "data-dir", default_value = "./data")]
+        data_dir: PathBuf,
+        #[command(flatten)]
+        filter: CardFilter,
+    },
+    /// Join previously pulled cards of several languages by card id
+    Merge {
+        /// Root directory of the pulled data
+        #[arg(short, long = "data-dir", default_value = "./data")]
+        data_dir: PathBuf,
+        /// Languages to merge, the first one having a card provides its stats
+        #[arg(
+            long = "languages",
+            alias = "langs",
+            value_delimiter = ',',
+            value_enum,
+            required = true
+        )]
+        languages: Vec<LanguageCode>,
+        /// File to write the merged cards to (defaults to stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Show what changed between two pulls
+    Diff {
+        /// Root directory of the previous pull, or a `cards_<pack>.json` file
+        old: PathBuf,
+        /// Root directory of the new pull, or a `cards_<pack>.json` file
+        new: PathBuf,
+    },
+    /// Export previously pulled data to another format
+    Export {
+        #[command(subcommand)]
+        format: ExportFormat,
     },
     /// Launch into interactive mode
     #[command(name = "inter", alias = "interactive", alias = "int")]
     Interactive,
-    /// Test what configuration files are found
+    /// Scrape a known pack and report which selectors no longer extract the expected data
+    Doctor {
+        /// Snapshot to compare against instead of the built-in one of the language
+        #[arg(long)]
+        snapshot: Option<PathBuf>,
+    },
+    /// Check every locale and report which ones are broken
     #[command(name = "test-config", alias = "test-conf")]
     TestConfig,
 }
 
+#[derive(Debug, Subcommand)]
+pub enum ExportFormat {
+    /// Build a SQLite database of packs and cards
+    Sqlite {
+        /// Root directory of the pulled data
+        #[arg(short, long = "data-dir", default_value = "./data")]
+        data_dir: PathBuf,
+        /// Database file to create or update
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Languages to export (defaults to the global language)
+        #[arg(long = "languages", alias = "langs", value_delimiter = ',', value_enum)]
+        languages: Vec<LanguageCode>,
+    },
+}
+
 #[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
 pub enum LanguageCode {
     #[value(name = "chinese-hongkong", alias = "zh_hk", alias = "zh_HK")]
@@ -0,0 +1,548 @@
+use anyhow::{bail, Context, Result};
+use log::{debug, info};
+use serde::Serialize;
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Write},
+    path::Path,
+    time::Instant,
+};
+
+use crate::{
+    card::Card,
+    cli::LanguageCode,
+    output::{self, OutputFormat},
+    pack::Pack,
+    storage::DataStore,
+};
+
+/// Value of a field before and after, `field` is a dotted path for nested values
+/// (e.g. `title_parts.label`).
+#[derive(Debug, Serialize, PartialEq)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PackChange {
+    pub id: String,
+    pub changes: Vec<FieldChange>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CardChange {
+    pub id: String,
+    pub pack_id: String,
+    pub name: String,
+    pub changes: Vec<FieldChange>,
+}
+
+/// Card added or removed, identified by the pack it is listed in.
+#[derive(Debug, Serialize)]
+pub struct CardEntry {
+    pub id: String,
+    pub pack_id: String,
+    pub name: String,
+}
+
+impl From<&Card> for CardEntry {
+    fn from(card: &Card) -> Self {
+        CardEntry {
+            id: card.id.clone(),
+            pack_id: card.pack_id.clone(),
+            name: card.name.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct DataDiff {
+    pub added_packs: Vec<String>,
+    pub removed_packs: Vec<String>,
+    pub changed_packs: Vec<PackChange>,
+    pub added_cards: Vec<CardEntry>,
+    pub removed_cards: Vec<CardEntry>,
+    pub changed_cards: Vec<CardChange>,
+}
+
+/// One line of the `ndjson` output, tagged with the kind of change.
+#[derive(Debug, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+enum DiffRecord<'a> {
+    AddedPack { id: &'a str },
+    RemovedPack { id: &'a str },
+    ChangedPack(&'a PackChange),
+    AddedCard(&'a CardEntry),
+    RemovedCard(&'a CardEntry),
+    ChangedCard(&'a CardChange),
+}
+
+impl DataDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_packs.is_empty()
+            && self.removed_packs.is_empty()
+            && self.changed_packs.is_empty()
+            && self.added_cards.is_empty()
+            && self.removed_cards.is_empty()
+            && self.changed_cards.is_empty()
+    }
+
+    fn records(&self) -> Vec<DiffRecord<'_>> {
+        let packs = self
+            .added_packs
+            .iter()
+            .map(|id| DiffRecord::AddedPack { id })
+            .chain(
+                self.removed_packs
+                    .iter()
+                    .map(|id| DiffRecord::RemovedPack { id }),
+            )
+            .chain(self.changed_packs.iter().map(DiffRecord::ChangedPack));
+        let cards = self
+            .added_cards
+            .iter()
+            .map(DiffRecord::AddedCard)
+            .chain(self.removed_cards.iter().map(DiffRecord::RemovedCard))
+            .chain(self.changed_cards.iter().map(DiffRecord::ChangedCard));
+
+        packs.chain(cards).collect()
+    }
+
+    /// Writes the differences as text when no format is given, as a single JSON object or as
+    /// one JSON object per change for `ndjson`.
+    pub fn write<W: Write>(&self, mut writer: W, format: Option<OutputFormat>) -> Result<()> {
+        match format {
+            None => self.write_text(writer),
+            Some(OutputFormat::Json) => {
+                serde_json::to_writer(&mut writer, self)?;
+                writeln!(writer)?;
+                Ok(())
+            }
+            Some(OutputFormat::JsonPretty) => {
+                serde_json::to_writer_pretty(&mut writer, self)?;
+                writeln!(writer)?;
+                Ok(())
+            }
+            Some(OutputFormat::Ndjson) => {
+                output::write_json(writer, &self.records(), OutputFormat::Ndjson)
+            }
+            Some(OutputFormat::Csv | OutputFormat::Tsv) => {
+                bail!("differences can only be written as text or JSON")
+            }
+        }
+    }
+
+    pub fn write_text<W: Write>(&self, mut writer: W) -> Result<()> {
+        if self.is_empty() {
+            writeln!(writer, "No differences")?;
+            return Ok(());
+        }
+
+        writeln!(
+            writer,
+            "packs: {} added, {} removed, {} changed",
+            self.added_packs.len(),
+            self.removed_packs.len(),
+            self.changed_packs.len()
+        )?;
+        for id in self.added_packs.iter() {
+            writeln!(writer, "+ {}", id)?;
+        }
+        for id in self.removed_packs.iter() {
+            writeln!(writer, "- {}", id)?;
+        }
+        for pack in self.changed_packs.iter() {
+            writeln!(writer, "~ {}", pack.id)?;
+            Self::write_changes(&mut writer, &pack.changes)?;
+        }
+
+        writeln!(
+            writer,
+            "cards: {} added, {} removed, {} changed",
+            self.added_cards.len(),
+            self.removed_cards.len(),
+            self.changed_cards.len()
+        )?;
+        for card in self.added_cards.iter() {
+            writeln!(writer, "+ {} [{}] {}", card.id, card.pack_id, card.name)?;
+        }
+        for card in self.removed_cards.iter() {
+            writeln!(writer, "- {} [{}] {}", card.id, card.pack_id, card.name)?;
+        }
+        for card in self.changed_cards.iter() {
+            writeln!(writer, "~ {} [{}] {}", card.id, card.pack_id, card.name)?;
+            Self::write_changes(&mut writer, &card.changes)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_changes<W: Write>(writer: &mut W, changes: &[FieldChange]) -> Result<()> {
+        for change in changes {
+            writeln!(
+                writer,
+                "    {}: {} -> {}",
+                change.field, change.old, change.new
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Lists the fields whose serialized value differs between `old` and `new`.
+pub fn diff_fields<T: Serialize>(old: &T, new: &T) -> Result<Vec<FieldChange>> {
+    let mut changes = Vec::new();
+    collect_changes(
+        "",
+        &serde_json::to_value(old)?,
+        &serde_json::to_value(new)?,
+        &mut changes,
+    );
+    Ok(changes)
+}
+
+fn collect_changes(path: &str, old: &Value, new: &Value, changes: &mut Vec<FieldChange>) {
+    if old == new {
+        return;
+    }
+
+    if let (Value::Object(old), Value::Object(new)) = (old, new) {
+        let mut fields: Vec<&String> = old.keys().chain(new.keys()).collect();
+        fields.sort();
+        fields.dedup();
+
+        for field in fields {
+            let field_path = if path.is_empty() {
+                field.to_string()
+            } else {
+                format!("{}.{}", path, field)
+            };
+
+            let old_value = old.get(field).unwrap_or(&Value::Null);
+            let new_value = new.get(field).unwrap_or(&Value::Null);
+            collect_changes(&field_path, old_value, new_value, changes);
+        }
+
+        return;
+    }
+
+    changes.push(FieldChange {
+        field: path.to_string(),
+        old: old.clone(),
+        new: new.clone(),
+    });
+}
+
+/// Compares two pulls, cards are matched by pack and id so a reprint in a new pack shows up as
+/// an added card.
+pub fn compare(
+    old_packs: &[Pack],
+    new_packs: &[Pack],
+    old_cards: &[Card],
+    new_cards: &[Card],
+) -> Result<DataDiff> {
+    let mut diff = DataDiff::default();
+
+    let old_packs_by_id: HashMap<&str, &Pack> = old_packs
+        .iter()
+        .map(|pack| (pack.id.as_str(), pack))
+        .collect();
+    let new_pack_ids: Vec<&str> = new_packs.iter().map(|pack| pack.id.as_str()).collect();
+
+    for pack in new_packs {
+        match old_packs_by_id.get(pack.id.as_str()) {
+            Some(old_pack) => {
+                let changes = diff_fields(*old_pack, pack)?;
+                if !changes.is_empty() {
+                    diff.changed_packs.push(PackChange {
+                        id: pack.id.clone(),
+                        changes,
+                    });
+                }
+            }
+            None => diff.added_packs.push(pack.id.clone()),
+        }
+    }
+
+    for pack in old_packs {
+        if !new_pack_ids.contains(&pack.id.as_str()) {
+            diff.removed_packs.push(pack.id.clone());
+        }
+    }
+
+    let card_key = |card: &Card| (card.pack_id.clone(), card.id.clone());
+    let old_cards_by_key: HashMap<(String, String), &Card> = old_cards
+        .iter()
+        .map(|card| (card_key(card), card))
+        .collect();
+    let new_cards_by_key: HashMap<(String, String), &Card> = new_cards
+        .iter()
+        .map(|card| (card_key(card), card))
+        .collect();
+
+    for card in new_cards {
+        match old_cards_by_key.get(&card_key(card)) {
+            Some(old_card) => {
+                let changes = diff_fields(*old_card, card)?;
+                if !changes.is_empty() {
+                    diff.changed_cards.push(CardChange {
+                        id: card.id.clone(),
+                        pack_id: card.pack_id.clone(),
+                        name: card.name.clone(),
+                        changes,
+                    });
+                }
+            }
+            None => diff.added_cards.push(CardEntry::from(card)),
+        }
+    }
+
+    for card in old_cards {
+        if !new_cards_by_key.contains_key(&card_key(card)) {
+            diff.removed_cards.push(CardEntry::from(card));
+        }
+    }
+
+    Ok(diff)
+}
+
+/// Packs and cards of one side of the comparison, a cards file has no packs.
+fn load_side(path: &Path, language: LanguageCode) -> Result<(Vec<Pack>, Vec<Card>)> {
+    if path.is_file() {
+        debug!("load cards file: {}", path.display());
+        let json = fs::read_to_string(path)
+            .with_context(|| format!("Failed to open file: {}", path.display()))?;
+        let mut cards: Vec<Card> = serde_json::from_str(&json)
+            .with_context(|| format!("Failed to parse cards: {}", path.display()))?;
+        for card in cards.iter_mut() {
+            card.normalize()?;
+        }
+
+        return Ok((Vec::new(), cards));
+    }
+
+    let store = DataStore::new(path, language);
+    let packs = store.load_packs()?;
+
+    let mut cards = Vec::new();
+    for pack in packs.iter() {
+        cards.extend(store.load_cards(&pack.id)?);
+    }
+
+    Ok((packs, cards))
+}
+
+/// Prints what changed between two data directories (for `language`) or two cards files.
+pub fn diff_data(
+    old: &Path,
+    new: &Path,
+    language: LanguageCode,
+    format: Option<OutputFormat>,
+) -> Result<()> {
+    if let Some(OutputFormat::Csv | OutputFormat::Tsv) = format {
+        bail!("differences can only be written as text or JSON");
+    }
+
+    if old.is_file() != new.is_file() {
+        bail!(
+            "cannot compare `{}` with `{}`, expected two data directories or two cards files",
+            old.display(),
+            new.display()
+        );
+    }
+
+    info!("comparing `{}` with `{}`...", old.display(), new.display());
+    let start = Instant::now();
+
+    let (old_packs, old_cards) = load_side(old, language)?;
+    let (new_packs, new_cards) = load_side(new, language)?;
+    let diff = compare(&old_packs, &new_packs, &old_cards, &new_cards)?;
+
+    diff.write(io::stdout().lock(), format)?;
+
+    let duration = start.elapsed();
+    info!("diff_data took: {:?}", duration);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::{test_util, CardRarity};
+
+    fn get_test_pack(id: &str, label: &str) -> Pack {
+        serde_json::from_str(&format!(
+            r#"{{"id":"{}","raw_title":"BOOSTER PACK -ROMANCE DAWN- [{}]",
+                "title_parts":{{"prefix":"BOOSTER PACK","title":"ROMANCE DAWN","label":"{}"}}}}"#,
+            id, label, label
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn diff_fields_lists_changed_fields() {
+        let old = test_util::card("OP01-016");
+        let mut new = test_util::card("OP01-016");
+        new.power = Some(3000);
+        new.rarity = CardRarity::SuperRare;
+
+        let changes = diff_fields(&old, &new).unwrap();
+
+        assert_eq!(
+            changes,
+            vec![
+                FieldChange {
+                    field: String::from("power"),
+                    old: Value::from(2000),
+                    new: Value::from(3000),
+                },
+                FieldChange {
+                    field: String::from("rarity"),
+                    old: Value::from("Rare"),
+                    new: Value::from("SuperRare"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_fields_uses_dotted_path_for_nested_values() {
+        let changes = diff_fields(
+            &get_test_pack("569101", "OP01"),
+            &get_test_pack("569101", "OP-01"),
+        )
+        .unwrap();
+
+        let fields: Vec<&str> = changes.iter().map(|c| c.field.as_str()).collect();
+        assert_eq!(fields, vec!["raw_title", "title_parts.label"]);
+    }
+
+    #[test]
+    fn compare_reports_added_removed_and_changed() {
+        let old_packs = vec![
+            get_test_pack("569101", "OP01"),
+            get_test_pack("569001", "ST01"),
+        ];
+        let new_packs = vec![
+            get_test_pack("569101", "OP01"),
+            get_test_pack("569102", "OP02"),
+        ];
+
+        let mut errata = test_util::card("OP01-016");
+        errata.effect = String::from("[On Play] Look at 3 cards from the top of your deck.");
+        let mut new_card = test_util::card("OP02-001");
+        new_card.pack_id = String::from("569102");
+
+        let old_cards = vec![test_util::card("OP01-016"), test_util::card("OP01-017")];
+        let new_cards = vec![errata, new_card];
+
+        let diff = compare(&old_packs, &new_packs, &old_cards, &new_cards).unwrap();
+
+        assert_eq!(diff.added_packs, vec![String::from("569102")]);
+        assert_eq!(diff.removed_packs, vec![String::from("569001")]);
+        assert!(diff.changed_packs.is_empty());
+        assert_eq!(diff.added_cards.len(), 1);
+        assert_eq!(diff.added_cards[0].id, "OP02-001");
+        assert_eq!(diff.removed_cards.len(), 1);
+        assert_eq!(diff.removed_cards[0].id, "OP01-017");
+        assert_eq!(diff.changed_cards.len(), 1);
+        assert_eq!(diff.changed_cards[0].changes[0].field, "effect");
+    }
+
+    #[test]
+    fn compare_reprint_in_new_pack_is_added() {
+        let mut reprint = test_util::card("OP01-016");
+        reprint.pack_id = String::from("569201");
+
+        let old_cards = vec![test_util::card("OP01-016")];
+        let new_cards = vec![test_util::card("OP01-016"), reprint];
+        let diff = compare(&[], &[], &old_cards, &new_cards).unwrap();
+
+        assert_eq!(diff.added_cards.len(), 1);
+        assert_eq!(diff.added_cards[0].pack_id, "569201");
+        assert!(diff.changed_cards.is_empty());
+    }
+
+    #[test]
+    fn write_text_lists_field_changes() {
+        let mut new = test_util::card("OP01-016");
+        new.cost = Some(2);
+        let diff = compare(&[], &[], &[test_util::card("OP01-016")], &[new]).unwrap();
+
+        let mut buffer = Vec::new();
+        diff.write_text(&mut buffer).unwrap();
+
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "packs: 0 added, 0 removed, 0 changed\n\
+             cards: 0 added, 0 removed, 1 changed\n\
+             ~ OP01-016 [569101] Nami\n    cost: 1 -> 2\n"
+        );
+    }
+
+    #[test]
+    fn write_text_without_changes() {
+        let cards = vec![test_util::card("OP01-016")];
+        let diff = compare(&[], &[], &cards, &cards).unwrap();
+
+        let mut buffer = Vec::new();
+        diff.write_text(&mut buffer).unwrap();
+
+        assert_eq!(String::from_utf8(buffer).unwrap(), "No differences\n");
+    }
+
+    #[test]
+    fn write_ndjson_prints_one_line_per_change() {
+        let mut new = test_util::card("OP01-016");
+        new.cost = Some(2);
+        let diff = compare(
+            &[],
+            &[get_test_pack("569102", "OP-02")],
+            &[test_util::card("OP01-016")],
+            &[new, test_util::card("OP01-017")],
+        )
+        .unwrap();
+
+        let mut buffer = Vec::new();
+        diff.write(&mut buffer, Some(OutputFormat::Ndjson)).unwrap();
+
+        let lines: Vec<Value> = String::from_utf8(buffer)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["change"], "added_pack");
+        assert_eq!(lines[0]["id"], "569102");
+        assert_eq!(lines[1]["change"], "added_card");
+        assert_eq!(lines[1]["id"], "OP01-017");
+        assert_eq!(lines[2]["change"], "changed_card");
+        assert_eq!(lines[2]["changes"][0]["field"], "cost");
+    }
+
+    #[test]
+    fn write_json_prints_single_object() {
+        let cards = vec![test_util::card("OP01-016")];
+        let diff = compare(&[], &[], &cards, &cards).unwrap();
+
+        let mut buffer = Vec::new();
+        diff.write(&mut buffer, Some(OutputFormat::Json)).unwrap();
+
+        let value: Value = serde_json::from_slice(&buffer).unwrap();
+        assert_eq!(value["changed_cards"], Value::Array(Vec::new()));
+    }
+
+    #[test]
+    fn write_csv_returns_err() {
+        let diff = DataDiff::default();
+
+        let mut buffer = Vec::new();
+        assert!(diff.write(&mut buffer, Some(OutputFormat::Csv)).is_err());
+        assert!(diff.write(&mut buffer, Some(OutputFormat::Tsv)).is_err());
+    }
+}
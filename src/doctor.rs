@@ -0,0 +1,439 @@
+use ::scraper::{ElementRef, Html, Selector};
+use anyhow::{bail, Context, Result};
+use log::{debug, info};
+use serde::Deserialize;
+use serde_json::Value;
+use std::{fs, path::Path, time::Instant};
+use yansi::Paint;
+
+use crate::{
+    card::{Card, CardScraper},
+    cli::LanguageCode,
+    error::VegaError,
+    fetcher::Fetcher,
+    localizer::Localizer,
+    pack::Pack,
+    policy::RequestOverrides,
+    scraper::OpTcgScraper,
+};
+
+/// Expected output for a known pack, keyed by locale name.
+const BUILTIN_SNAPSHOTS: [(&str, &str); 1] = [("en", include_str!("../golden/en.json"))];
+
+/// Known pack and the cards it is expected to contain, as printed by the `cards` command.
+#[derive(Debug, Deserialize)]
+pub struct Snapshot {
+    pub pack: Pack,
+    pub cards: Vec<Card>,
+}
+
+impl Snapshot {
+    /// Reads `path` if given, falling back to the snapshot built in for the locale of `language`.
+    pub fn load(path: Option<&Path>, language: LanguageCode) -> Result<Snapshot> {
+        let locale = Localizer::locale_name(language);
+        let json = match path {
+            Some(path) => {
+                info!("load snapshot from: {}", path.display());
+                fs::read_to_string(path).map_err(|e| VegaError::storage(path, e))?
+            }
+            None => match BUILTIN_SNAPSHOTS.iter().find(|(name, _)| *name == locale) {
+                Some((_, json)) => json.to_string(),
+                None => bail!(
+                    "no built-in snapshot for the `{}` locale, pass one with --snapshot",
+                    locale
+                ),
+            },
+        };
+
+        let snapshot = serde_json::from_str(&json).context("failed to parse snapshot")?;
+        Ok(snapshot)
+    }
+}
+
+/// Outcome of one selector over every card of the snapshot.
+#[derive(Debug)]
+pub struct SelectorReport {
+    pub field: &'static str,
+    pub selector: String,
+    pub problems: Vec<String>,
+}
+
+impl SelectorReport {
+    fn new(field: &'static str, selector: &str) -> Self {
+        SelectorReport {
+            field,
+            selector: selector.to_string(),
+            problems: Vec::new(),
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    fn compare(&mut self, subject: &str, expected: &Value, result: Result<Value>) {
+        self.compare_with(Comparison::Exact, subject, expected, result);
+    }
+
+    fn compare_with(
+        &mut self,
+        comparison: Comparison,
+        subject: &str,
+        expected: &Value,
+        result: Result<Value>,
+    ) {
+        match result {
+            Ok(ref actual) if comparison.matches(expected, actual) => {}
+            Ok(actual) => self.problems.push(format!(
+                "{}: expected `{}`, got `{}`",
+                subject, expected, actual
+            )),
+            Err(e) => self.problems.push(format!("{}: {:#}", subject, e)),
+        }
+    }
+}
+
+/// How an extracted value is checked against the snapshot, the site changes some values on its
+/// own so only their shape tells whether the selector still works.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Comparison {
+    Exact,
+    /// Urls compared without their query, which holds a cache-buster bumped with every update
+    UrlPath,
+    /// Texts that errata can reword, only their presence is compared
+    Presence,
+}
+
+impl Comparison {
+    fn matches(self, expected: &Value, actual: &Value) -> bool {
+        match self {
+            Comparison::Exact => expected == actual,
+            Comparison::UrlPath => {
+                let path = |value: &Value| {
+                    value
+                        .as_str()
+                        .map(|url| url.split('?').next().unwrap_or_default().to_string())
+                };
+
+                path(expected).is_some() && path(expected) == path(actual)
+            }
+            Comparison::Presence => {
+                let is_present = |value: &Value| match value {
+                    Value::Null => false,
+                    Value::String(text) => !text.trim().is_empty(),
+                    _ => true,
+                };
+
+                is_present(expected) == is_present(actual)
+            }
+        }
+    }
+}
+
+type Extractor = fn(&Localizer, ElementRef) -> Result<Value>;
+
+macro_rules! extractor {
+    ($fetch:path) => {
+        |localizer, element| Ok(serde_json::to_value($fetch(localizer, element)?)?)
+    };
+}
+
+/// Every field scraped from the `<dl>` node of a card, keyed like its `[selectors]` entry.
+fn card_extractors() -> Vec<(&'static str, Extractor, Comparison)> {
+    use Comparison::*;
+
+    vec![
+        ("name", extractor!(CardScraper::fetch_name), Exact),
+        ("rarity", extractor!(CardScraper::fetch_rarity), Exact),
+        ("category", extractor!(CardScraper::fetch_category), Exact),
+        ("img_url", extractor!(CardScraper::fetch_img_url), UrlPath),
+        ("colors", extractor!(CardScraper::fetch_colors), Exact),
+        ("cost", extractor!(CardScraper::fetch_cost), Exact),
+        (
+            "attributes",
+            extractor!(CardScraper::fetch_attributes),
+            Exact,
+        ),
+        ("power", extractor!(CardScraper::fetch_power), Exact),
+        ("counter", extractor!(CardScraper::fetch_counter), Exact),
+        ("types", extractor!(CardScraper::fetch_types), Exact),
+        ("effect", extractor!(CardScraper::fetch_effect), Presence),
+        ("trigger", extractor!(CardScraper::fetch_trigger), Presence),
+        (
+            "illustration",
+            extractor!(CardScraper::fetch_illustration),
+            Exact,
+        ),
+        (
+            "illustrator_name",
+            extractor!(CardScraper::fetch_illustrator_name),
+            Exact,
+        ),
+        ("block", extractor!(CardScraper::fetch_block), Exact),
+        ("product", extractor!(CardScraper::fetch_product), Exact),
+    ]
+}
+
+fn select<'a>(document: &'a Html, sel: &str) -> Vec<ElementRef<'a>> {
+    let selector = Selector::parse(sel).unwrap();
+    document.select(&selector).collect()
+}
+
+fn check_packs(document: &Html, sel: &str, expected: &Pack) -> Result<SelectorReport> {
+    let mut report = SelectorReport::new("packs", sel);
+
+    let options = select(document, sel);
+    if options.is_empty() {
+        report.problems.push(String::from("matched nothing"));
+        return Ok(report);
+    }
+
+    match options
+        .iter()
+        .find(|o| o.value().attr("value") == Some(expected.id.as_str()))
+    {
+        Some(option) => report.compare(
+            &format!("pack `{}`", expected.id),
+            &serde_json::to_value(expected)?,
+            Pack::new(*option).and_then(|pack| Ok(serde_json::to_value(pack)?)),
+        ),
+        None => report.problems.push(format!(
+            "pack `{}` not found among {} options",
+            expected.id,
+            options.len()
+        )),
+    }
+
+    Ok(report)
+}
+
+fn check_card_links(document: &Html, sel: &str, expected: &[Card]) -> Result<SelectorReport> {
+    let mut report = SelectorReport::new("card_links", sel);
+
+    let ids: Vec<&str> = select(document, sel)
+        .iter()
+        .filter_map(|link| link.value().attr("data-src"))
+        .map(|data_src| data_src.trim_start_matches('#'))
+        .collect();
+    let expected_ids: Vec<&str> = expected.iter().map(|card| card.id.as_str()).collect();
+
+    report.compare(
+        "card ids",
+        &serde_json::to_value(expected_ids)?,
+        Ok(serde_json::to_value(ids)?),
+    );
+    Ok(report)
+}
+
+/// Runs every extractor on the snapshot pack and reports, for each selector, the cards it no
+/// longer extracts as expected.
+pub fn run_checks<F: Fetcher>(
+    scraper: &OpTcgScraper<F>,
+    localizer: &Localizer,
+    snapshot: &Snapshot,
+) -> Result<Vec<SelectorReport>> {
+    let selectors = &localizer.selectors;
+    let mut reports = Vec::new();
+
+    let document = scraper.fetch_cardlist_page(None)?;
+    reports.push(check_packs(&document, &selectors.packs, &snapshot.pack)?);
+
+    let document = scraper.fetch_cardlist_page(Some(&snapshot.pack.id))?;
+    reports.push(check_card_links(
+        &document,
+        &selectors.card_links,
+        &snapshot.cards,
+    )?);
+
+    let mut node_report = SelectorReport::new("card", &selectors.card);
+    let extractors = card_extractors();
+    let mut field_reports: Vec<SelectorReport> = extractors
+        .iter()
+        .map(|(field, _, _)| SelectorReport::new(field, selectors.get(field).unwrap_or_default()))
+        .collect();
+
+    for card in snapshot.cards.iter() {
+        debug!("checking card `{}`", card.id);
+        let dl_elem = match CardScraper::get_dl_node(localizer, &document, &card.id) {
+            Ok(dl_elem) => dl_elem,
+            Err(e) => {
+                node_report.problems.push(format!("{}: {:#}", card.id, e));
+                continue;
+            }
+        };
+
+        let expected = serde_json::to_value(card)?;
+        for ((field, extract, comparison), report) in
+            extractors.iter().zip(field_reports.iter_mut())
+        {
+            let result = extract(localizer, dl_elem);
+            report.compare_with(*comparison, &card.id, &expected[field], result);
+        }
+    }
+
+    reports.push(node_report);
+    reports.extend(field_reports);
+    Ok(reports)
+}
+
+/// Scrapes the snapshot pack from the site and prints which selectors still match, failing
+/// with the first broken one.
+pub fn run_doctor(
+    config_dir: &Path,
+    fetcher: &dyn Fetcher,
+    request: RequestOverrides,
+    language: LanguageCode,
+    snapshot_path: Option<&Path>,
+) -> Result<()> {
+    let localizer = Localizer::load(config_dir, language)?;
+    let policy = localizer.request.clone().with_overrides(request);
+    let scraper = OpTcgScraper::new(&localizer, fetcher).with_policy(policy);
+    let snapshot = Snapshot::load(snapshot_path, language)?;
+
+    info!(
+        "checking extractors against pack `{}` ({} cards)...",
+        snapshot.pack.id,
+        snapshot.cards.len()
+    );
+    let start = Instant::now();
+
+    let reports = run_checks(&scraper, &localizer, &snapshot)?;
+    println!("pack: {} ({})", snapshot.pack.raw_title, snapshot.pack.id);
+
+    for report in reports.iter() {
+        let status = if report.is_ok() {
+            "OK".green()
+        } else {
+            "FAILED".red()
+        };
+        println!("{} `{}`: {}", report.field, report.selector, status);
+        for problem in report.problems.iter() {
+            println!("  - {}", problem);
+        }
+    }
+
+    let duration = start.elapsed();
+    info!("run_doctor took: {:?}", duration);
+
+    let broken: Vec<&SelectorReport> = reports.iter().filter(|r| !r.is_ok()).collect();
+    if let Some(first) = broken.first() {
+        bail!(VegaError::Selector {
+            selector: first.selector.clone(),
+            problem: format!(
+                "no longer extracts the expected {} ({} selector(s) broken)",
+                first.field,
+                broken.len()
+            ),
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fetcher::{FixtureFetcher, MemoryFetcher};
+
+    const FIXTURES_DIR: &str = "tests/fixtures/en";
+
+    fn get_test_localizer() -> Localizer {
+        Localizer::load(Path::new("config"), LanguageCode::English).unwrap()
+    }
+
+    fn get_renamed_class_fetcher(localizer: &Localizer) -> MemoryFetcher {
+        let url = format!("{}/cardlist", localizer.hostname);
+        let read = |name: &str| fs::read_to_string(Path::new(FIXTURES_DIR).join(name)).unwrap();
+
+        let mut fetcher = MemoryFetcher::new();
+        fetcher.insert_html(&url, &[], &read("cardlist.html"));
+        fetcher.insert_html(
+            &url,
+            &[("series", "569101")],
+            &read("cardlist__series=569101.html").replace("cardName", "cardTitle"),
+        );
+
+        fetcher
+    }
+
+    #[test]
+    fn run_checks_fixtures_returns_ok() {
+        let localizer = get_test_localizer();
+        let fetcher = FixtureFetcher::new(Path::new(FIXTURES_DIR)).unwrap();
+        let scraper = OpTcgScraper::new(&localizer, fetcher);
+        let snapshot = Snapshot::load(None, LanguageCode::English).unwrap();
+
+        let reports = run_checks(&scraper, &localizer, &snapshot).unwrap();
+        let broken: Vec<_> = reports.iter().filter(|r| !r.is_ok()).collect();
+        assert!(broken.is_empty(), "{:?}", broken);
+    }
+
+    #[test]
+    fn run_checks_renamed_class_reports_selector() {
+        let localizer = get_test_localizer();
+        let scraper = OpTcgScraper::new(&localizer, get_renamed_class_fetcher(&localizer));
+        let snapshot = Snapshot::load(None, LanguageCode::English).unwrap();
+
+        let reports = run_checks(&scraper, &localizer, &snapshot).unwrap();
+        let broken: Vec<_> = reports.iter().filter(|r| !r.is_ok()).collect();
+
+        assert_eq!(broken.len(), 1);
+        assert_eq!(broken[0].field, "name");
+        assert_eq!(broken[0].selector, localizer.selectors.name);
+        assert_eq!(broken[0].problems.len(), snapshot.cards.len());
+    }
+
+    #[test]
+    fn run_checks_ignores_cache_buster_and_errata() {
+        let localizer = get_test_localizer();
+        let url = format!("{}/cardlist", localizer.hostname);
+        let read = |name: &str| fs::read_to_string(Path::new(FIXTURES_DIR).join(name)).unwrap();
+
+        let mut fetcher = MemoryFetcher::new();
+        fetcher.insert_html(&url, &[], &read("cardlist.html"));
+        fetcher.insert_html(
+            &url,
+            &[("series", "569101")],
+            &read("cardlist__series=569101.html")
+                .replace("?240322", "?991231")
+                .replace("Look at 5 cards", "Look at 4 cards"),
+        );
+
+        let scraper = OpTcgScraper::new(&localizer, fetcher);
+        let snapshot = Snapshot::load(None, LanguageCode::English).unwrap();
+
+        let reports = run_checks(&scraper, &localizer, &snapshot).unwrap();
+        let broken: Vec<_> = reports.iter().filter(|r| !r.is_ok()).collect();
+        assert!(broken.is_empty(), "{:?}", broken);
+    }
+
+    #[test]
+    fn comparison_url_path_ignores_query() {
+        let expected = Value::from("../images/cardlist/card/OP01-001.png?240322");
+
+        assert!(Comparison::UrlPath.matches(
+            &expected,
+            &Value::from("../images/cardlist/card/OP01-001.png?250101")
+        ));
+        assert!(!Comparison::UrlPath.matches(
+            &expected,
+            &Value::from("../images/cardlist/card/OP01-016.png?240322")
+        ));
+        assert!(!Comparison::UrlPath.matches(&expected, &Value::Null));
+    }
+
+    #[test]
+    fn comparison_presence_checks_emptiness() {
+        let expected = Value::from("[On Play] Draw 1 card.");
+
+        assert!(Comparison::Presence.matches(&expected, &Value::from("[On Play] Draw 2 cards.")));
+        assert!(!Comparison::Presence.matches(&expected, &Value::from("")));
+        assert!(Comparison::Presence.matches(&Value::Null, &Value::Null));
+        assert!(!Comparison::Presence.matches(&Value::Null, &Value::from("Activate")));
+    }
+
+    #[test]
+    fn snapshot_load_without_builtin_returns_err() {
+        assert!(Snapshot::load(None, LanguageCode::Japanese).is_err());
+    }
+}
@@ -0,0 +1,194 @@
+use anyhow::{anyhow, Context, Result};
+use log::{debug, info};
+use std::{
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    thread,
+};
+
+use crate::{
+    card::Card,
+    fetcher::{FetchResponse, Fetcher},
+    product::ProductInfo,
+    scraper::OpTcgScraper,
+};
+
+/// Downloads the images of `cards` with at most `jobs` worker threads.
+///
+/// `on_image` is called from the worker that downloaded the image, along with the number of
+/// images completed so far. The first failure stops the remaining downloads and is returned.
+pub fn download_card_images<F, H>(
+    scraper: &OpTcgScraper<F>,
+    cards: &[Card],
+    jobs: usize,
+    on_image: H,
+) -> Result<()>
+where
+    F: Fetcher,
+    H: Fn(usize, &Card, FetchResponse) -> Result<()> + Sync,
+{
+    download_all(
+        cards,
+        jobs,
+        |card| scraper.download_card_image(card),
+        on_image,
+        |card| format!("failed to download image for card `{}`", card.id),
+    )
+}
+
+/// Downloads the box art of the packs of `products`, the same way as [`download_card_images`].
+pub fn download_pack_images<F, H>(
+    scraper: &OpTcgScraper<F>,
+    products: &[ProductInfo],
+    jobs: usize,
+    on_image: H,
+) -> Result<()>
+where
+    F: Fetcher,
+    H: Fn(usize, &ProductInfo, FetchResponse) -> Result<()> + Sync,
+{
+    download_all(
+        products,
+        jobs,
+        |product| scraper.download_pack_image(product),
+        on_image,
+        |product| format!("failed to download image for pack `{}`", product.pack_id),
+    )
+}
+
+fn download_all<T, D, H, C>(
+    items: &[T],
+    jobs: usize,
+    download: D,
+    on_image: H,
+    context: C,
+) -> Result<()>
+where
+    T: Sync,
+    D: Fn(&T) -> Result<FetchResponse> + Sync,
+    H: Fn(usize, &T, FetchResponse) -> Result<()> + Sync,
+    C: Fn(&T) -> String + Sync,
+{
+    let jobs = jobs.clamp(1, items.len().max(1));
+    info!("downloading {} images using {} job(s)", items.len(), jobs);
+
+    let next_idx = AtomicUsize::new(0);
+    let done_count = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+
+    let worker = || -> Result<()> {
+        loop {
+            if failed.load(Ordering::SeqCst) {
+                debug!("stopping worker, another download failed");
+                return Ok(());
+            }
+
+            let idx = next_idx.fetch_add(1, Ordering::SeqCst);
+            let Some(item) = items.get(idx) else {
+                return Ok(());
+            };
+
+            let result = download(item).and_then(|img_data| {
+                let done = done_count.fetch_add(1, Ordering::SeqCst) + 1;
+                on_image(done, item, img_data)
+            });
+
+            if let Err(e) = result {
+                failed.store(true, Ordering::SeqCst);
+                return Err(e).with_context(|| context(item));
+            }
+        }
+    };
+
+    let results: Vec<Result<()>> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..jobs).map(|_| scope.spawn(worker)).collect();
+
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|_| Err(anyhow!("image download worker panicked")))
+            })
+            .collect()
+    });
+
+    results.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cli::LanguageCode, fetcher::FixtureFetcher, localizer::Localizer};
+    use std::{path::Path, sync::Mutex};
+
+    fn get_test_localizer() -> Localizer {
+        Localizer::load(Path::new("config"), LanguageCode::English).unwrap()
+    }
+
+    #[test]
+    fn download_card_images_visits_every_card() {
+        let localizer = get_test_localizer();
+        let fetcher = FixtureFetcher::new(Path::new("tests/fixtures/en")).unwrap();
+        let scraper = OpTcgScraper::new(&localizer, fetcher);
+        let cards = scraper.fetch_all_cards("569101").unwrap();
+
+        let visited = Mutex::new(Vec::new());
+        download_card_images(&scraper, &cards, 4, |done, card, img_data| {
+            assert!(done <= cards.len());
+            assert!(!img_data.body.is_empty());
+            visited.lock().unwrap().push(card.id.clone());
+            Ok(())
+        })
+        .unwrap();
+
+        let mut visited = visited.into_inner().unwrap();
+        visited.sort();
+        assert_eq!(visited, vec!["OP01-001", "OP01-016", "OP01-029"]);
+    }
+
+    #[test]
+    fn download_card_images_returns_first_err() {
+        let localizer = get_test_localizer();
+        let fetcher = FixtureFetcher::new(Path::new("tests/fixtures/en")).unwrap();
+        let scraper = OpTcgScraper::new(&localizer, fetcher);
+        let mut cards = scraper.fetch_all_cards("569101").unwrap();
+        cards[1].img_url = String::from("../images/cardlist/card/missing.png");
+
+        let result = download_card_images(&scraper, &cards, 2, |_, _, _| Ok(()));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn download_pack_images_visits_every_product() {
+        let localizer = get_test_localizer();
+        let fetcher = FixtureFetcher::new(Path::new("tests/fixtures/en")).unwrap();
+        let scraper = OpTcgScraper::new(&localizer, fetcher);
+        let packs = scraper.fetch_all_packs().unwrap();
+        let products = scraper.fetch_all_products(&packs).unwrap();
+
+        let visited = Mutex::new(Vec::new());
+        download_pack_images(&scraper, &products, 2, |_, product, img_data| {
+            assert!(img_data.body.starts_with(b"\x89PNG"));
+            visited.lock().unwrap().push(product.pack_id.clone());
+            Ok(())
+        })
+        .unwrap();
+
+        let mut visited = visited.into_inner().unwrap();
+        visited.sort();
+        assert_eq!(visited, vec!["569001", "569101"]);
+    }
+
+    #[test]
+    fn download_pack_images_returns_first_err() {
+        let localizer = get_test_localizer();
+        let fetcher = FixtureFetcher::new(Path::new("tests/fixtures/en")).unwrap();
+        let scraper = OpTcgScraper::new(&localizer, fetcher);
+        let packs = scraper.fetch_all_packs().unwrap();
+        let mut products = scraper.fetch_all_products(&packs).unwrap();
+        products[0].img_url = Some(String::from("../images/products/missing.png"));
+
+        let err = download_pack_images(&scraper, &products, 1, |_, _, _| Ok(())).unwrap_err();
+        assert!(format!("{:#}", err).contains("pack `569101`"));
+    }
+}
@@ -0,0 +1,175 @@
+use std::{fmt, io, path::PathBuf};
+
+use thiserror::Error;
+
+/// Failures callers need to tell apart, e.g. to decide whether to retry or alert.
+///
+/// They are raised through `anyhow` like every other error and can be found back with
+/// [`VegaError::find`], whatever context was added on the way up.
+#[derive(Debug, Error)]
+pub enum VegaError {
+    /// The site could not be reached or the connection dropped
+    #[error("request to `{url}` failed")]
+    Network {
+        url: String,
+        #[source]
+        source: reqwest::Error,
+    },
+    /// The site answered with an unexpected status code
+    #[error("GET `{url}` failed: HTTP {status}")]
+    HttpStatus { url: String, status: u16 },
+    /// The page no longer has the expected structure
+    #[error("selector `{selector}` {problem}")]
+    Selector { selector: String, problem: String },
+    /// A label shown by the site is missing from the locale
+    #[error("no {section} key matches `{value}`")]
+    Localization {
+        section: &'static str,
+        value: String,
+    },
+    /// A scraped or stored value has an unexpected format
+    #[error("failed to parse {field} `{value}`: {reason}")]
+    Parse {
+        field: String,
+        value: String,
+        reason: String,
+    },
+    /// A locale could not be loaded
+    #[error("invalid {locale} locale: {reason}")]
+    Config { locale: String, reason: String },
+    /// Reading or writing the data directory failed
+    #[error("failed to access `{}`", path.display())]
+    Storage {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+}
+
+impl VegaError {
+    pub fn find(error: &anyhow::Error) -> Option<&VegaError> {
+        error
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<VegaError>())
+    }
+
+    /// Process exit code, `1` is left for other failures and `2` for invalid arguments.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            VegaError::Network { .. } => 3,
+            VegaError::HttpStatus { .. } => 4,
+            VegaError::Selector { .. } => 5,
+            VegaError::Localization { .. } => 6,
+            VegaError::Parse { .. } => 7,
+            VegaError::Config { .. } => 8,
+            VegaError::Storage { .. } => 9,
+        }
+    }
+
+    pub fn selector_missing(selector: &str) -> Self {
+        VegaError::Selector {
+            selector: selector.to_string(),
+            problem: String::from("matched nothing"),
+        }
+    }
+
+    pub fn selector_missing_attr(selector: &str, attr: &str) -> Self {
+        VegaError::Selector {
+            selector: selector.to_string(),
+            problem: format!("has no `{}` attribute", attr),
+        }
+    }
+
+    pub fn storage(path: impl Into<PathBuf>, source: io::Error) -> Self {
+        VegaError::Storage {
+            path: path.into(),
+            source,
+        }
+    }
+}
+
+/// Pack and card being scraped when an error happened, added as context.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScrapeLocation {
+    pub pack_id: Option<String>,
+    pub card_id: Option<String>,
+}
+
+impl ScrapeLocation {
+    pub fn pack(pack_id: &str) -> Self {
+        ScrapeLocation {
+            pack_id: Some(pack_id.to_string()),
+            card_id: None,
+        }
+    }
+
+    pub fn packs() -> Self {
+        ScrapeLocation {
+            pack_id: None,
+            card_id: None,
+        }
+    }
+
+    pub fn card(pack_id: &str, card_id: &str) -> Self {
+        ScrapeLocation {
+            pack_id: Some(pack_id.to_string()),
+            card_id: Some(card_id.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for ScrapeLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.pack_id, &self.card_id) {
+            (Some(pack_id), Some(card_id)) => write!(
+                f,
+                "failed to scrape data about card `{}` of pack `{}`",
+                card_id, pack_id
+            ),
+            (Some(pack_id), None) => write!(f, "failed to scrape data about pack `{}`", pack_id),
+            (None, _) => write!(f, "failed to scrape data about packs"),
+        }
+    }
+}
+
+/// Exit code for any error returned by a command.
+pub fn exit_code(error: &anyhow::Error) -> u8 {
+    VegaError::find(error).map_or(1, VegaError::exit_code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::{bail, Context, Result};
+
+    fn fail_with(error: VegaError) -> Result<()> {
+        bail!(error)
+    }
+
+    #[test]
+    fn find_through_context_returns_some() {
+        let err = fail_with(VegaError::selector_missing("dt>div.cardName"))
+            .context(ScrapeLocation::card("569101", "OP01-001"))
+            .context("failed to pull pack")
+            .unwrap_err();
+
+        let error = VegaError::find(&err).unwrap();
+        assert!(
+            matches!(error, VegaError::Selector { selector, .. } if selector == "dt>div.cardName")
+        );
+        assert_eq!(exit_code(&err), 5);
+
+        let location = err.downcast_ref::<ScrapeLocation>().unwrap();
+        assert_eq!(location.card_id.as_deref(), Some("OP01-001"));
+        assert_eq!(location.pack_id.as_deref(), Some("569101"));
+    }
+
+    #[test]
+    fn find_untyped_error_returns_none() {
+        let err = anyhow::anyhow!("something else").context("failed to pull pack");
+
+        assert!(VegaError::find(&err).is_none());
+        assert!(err.downcast_ref::<ScrapeLocation>().is_none());
+        assert_eq!(exit_code(&err), 1);
+    }
+}
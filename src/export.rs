@@ -0,0 +1,693 @@
+use anyhow::{ensure, Context, Result};
+use log::{debug, info};
+use rusqlite::{params, Connection, Transaction};
+use serde::Serialize;
+use std::{path::Path, time::Instant};
+
+use crate::{card::Card, cli::LanguageCode, pack::Pack, product::ProductInfo, storage::DataStore};
+
+const SQLITE_SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS packs (
+    language TEXT NOT NULL,
+    id TEXT NOT NULL,
+    raw_title TEXT NOT NULL,
+    prefix TEXT,
+    title TEXT NOT NULL,
+    label TEXT,
+    product_type TEXT NOT NULL,
+    set_code TEXT,
+    set_number INTEGER,
+    position INTEGER NOT NULL,
+    PRIMARY KEY (language, id)
+);
+
+CREATE TABLE IF NOT EXISTS products (
+    language TEXT NOT NULL,
+    pack_id TEXT NOT NULL,
+    title TEXT NOT NULL,
+    release_date TEXT,
+    img_url TEXT,
+    img_full_url TEXT,
+    price TEXT,
+    pack_size TEXT,
+    PRIMARY KEY (language, pack_id),
+    FOREIGN KEY (language, pack_id) REFERENCES packs (language, id)
+);
+
+CREATE TABLE IF NOT EXISTS cards (
+    language TEXT NOT NULL,
+    id TEXT NOT NULL,
+    base_id TEXT NOT NULL,
+    variant_kind TEXT,
+    variant_index INTEGER,
+    pack_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    rarity TEXT NOT NULL,
+    category TEXT NOT NULL,
+    block INTEGER,
+    product TEXT,
+    img_url TEXT NOT NULL,
+    img_full_url TEXT,
+    illustration TEXT,
+    illustrator_name TEXT,
+    cost INTEGER,
+    power INTEGER,
+    counter INTEGER,
+    effect TEXT NOT NULL,
+    trigger TEXT,
+    PRIMARY KEY (language, id),
+    FOREIGN KEY (language, pack_id) REFERENCES packs (language, id)
+);
+
+CREATE TABLE IF NOT EXISTS pack_cards (
+    language TEXT NOT NULL,
+    pack_id TEXT NOT NULL,
+    card_id TEXT NOT NULL,
+    PRIMARY KEY (language, pack_id, card_id),
+    FOREIGN KEY (language, pack_id) REFERENCES packs (language, id),
+    FOREIGN KEY (language, card_id) REFERENCES cards (language, id)
+);
+
+CREATE TABLE IF NOT EXISTS card_colors (
+    language TEXT NOT NULL,
+    card_id TEXT NOT NULL,
+    color TEXT NOT NULL,
+    PRIMARY KEY (language, card_id, color),
+    FOREIGN KEY (language, card_id) REFERENCES cards (language, id)
+);
+
+CREATE TABLE IF NOT EXISTS card_attributes (
+    language TEXT NOT NULL,
+    card_id TEXT NOT NULL,
+    attribute TEXT NOT NULL,
+    PRIMARY KEY (language, card_id, attribute),
+    FOREIGN KEY (language, card_id) REFERENCES cards (language, id)
+);
+
+CREATE TABLE IF NOT EXISTS card_types (
+    language TEXT NOT NULL,
+    card_id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    type TEXT NOT NULL,
+    PRIMARY KEY (language, card_id, position),
+    FOREIGN KEY (language, card_id) REFERENCES cards (language, id)
+);
+
+CREATE INDEX IF NOT EXISTS packs_by_product_type ON packs (language, product_type);
+CREATE INDEX IF NOT EXISTS cards_by_base_id ON cards (language, base_id);
+CREATE INDEX IF NOT EXISTS cards_by_cost ON cards (language, cost);
+CREATE INDEX IF NOT EXISTS card_colors_by_color ON card_colors (language, color);
+CREATE INDEX IF NOT EXISTS card_types_by_type ON card_types (language, type);
+";
+
+/// Version stored in `PRAGMA user_version` once the schema is up to date, bump it whenever
+/// `SQLITE_SCHEMA` changes and list the new or renamed columns below.
+const SQLITE_SCHEMA_VERSION: i64 = 2;
+
+/// Columns added to tables created by older versions, as `(table, column, definition)`.
+const SQLITE_ADDED_COLUMNS: [(&str, &str, &str); 10] = [
+    ("packs", "product_type", "TEXT NOT NULL DEFAULT 'Other'"),
+    ("packs", "set_code", "TEXT"),
+    ("packs", "set_number", "INTEGER"),
+    ("cards", "base_id", "TEXT NOT NULL DEFAULT ''"),
+    ("cards", "variant_kind", "TEXT"),
+    ("cards", "variant_index", "INTEGER"),
+    ("cards", "block", "INTEGER"),
+    ("cards", "product", "TEXT"),
+    ("cards", "illustration", "TEXT"),
+    ("cards", "illustrator_name", "TEXT"),
+];
+
+/// Columns renamed since older versions, as `(table, old name, new name)`.
+const SQLITE_RENAMED_COLUMNS: [(&str, &str, &str); 1] = [("packs", "release_order", "set_number")];
+
+/// Tables in deletion order, children first.
+const SQLITE_TABLES: [&str; 7] = [
+    "card_types",
+    "card_attributes",
+    "card_colors",
+    "pack_cards",
+    "products",
+    "cards",
+    "packs",
+];
+
+/// Builds or updates a SQLite database with the data pulled into `data_dir` for every language.
+///
+/// Each language replaces its own rows, so languages can be exported into the same database
+/// one at a time.
+pub fn export_sqlite(data_dir: &Path, languages: &[LanguageCode], db_path: &Path) -> Result<()> {
+    info!("exporting to SQLite database: {}", db_path.display());
+    let start = Instant::now();
+
+    let mut conn = Connection::open(db_path)
+        .with_context(|| format!("Failed to open database: {}", db_path.display()))?;
+
+    conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+    migrate_schema(&mut conn)?;
+
+    for language in languages {
+        let store = DataStore::new(data_dir, *language);
+        let packs = store.load_packs()?;
+        let products = store.load_products()?;
+
+        let mut cards = Vec::new();
+        for pack in packs.iter() {
+            cards.push((pack.id.as_str(), store.load_cards(&pack.id)?));
+        }
+
+        let tx = conn.transaction()?;
+        let card_count = write_language(&tx, *language, &packs, &products, &cards)?;
+        tx.commit()?;
+
+        println!(
+            "Exported {} packs and {} cards ({:?})",
+            packs.len(),
+            card_count,
+            language
+        );
+    }
+
+    let duration = start.elapsed();
+    info!("export_sqlite took: {:?}", duration);
+    Ok(())
+}
+
+/// Creates the tables, or brings the ones of a database exported by an older version up to date
+/// so the rows of the languages that are not exported again are kept.
+fn migrate_schema(conn: &mut Connection) -> Result<()> {
+    let version: i64 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if version == SQLITE_SCHEMA_VERSION {
+        debug!("database schema is up to date (version {})", version);
+        return Ok(());
+    }
+
+    ensure!(
+        version < SQLITE_SCHEMA_VERSION,
+        "database schema version {} is newer than the supported version {}",
+        version,
+        SQLITE_SCHEMA_VERSION
+    );
+
+    info!(
+        "migrating database schema from version {} to {}",
+        version, SQLITE_SCHEMA_VERSION
+    );
+
+    let tx = conn.transaction()?;
+    for (table, old_name, new_name) in SQLITE_RENAMED_COLUMNS {
+        if column_exists(&tx, table, old_name)? && !column_exists(&tx, table, new_name)? {
+            debug!(
+                "renaming column `{}` of {} to `{}`",
+                old_name, table, new_name
+            );
+            tx.execute_batch(&format!(
+                "ALTER TABLE {} RENAME COLUMN {} TO {};",
+                table, old_name, new_name
+            ))?;
+        }
+    }
+
+    for (table, column, definition) in SQLITE_ADDED_COLUMNS {
+        let table_exists: bool = tx.query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = ?1",
+            params![table],
+            |row| row.get(0),
+        )?;
+        if table_exists && !column_exists(&tx, table, column)? {
+            debug!("adding column `{}` to {}", column, table);
+            tx.execute_batch(&format!(
+                "ALTER TABLE {} ADD COLUMN {} {};",
+                table, column, definition
+            ))?;
+        }
+    }
+
+    tx.execute_batch(SQLITE_SCHEMA)?;
+
+    // Cards exported before variants existed are their own base card
+    tx.execute("UPDATE cards SET base_id = id WHERE base_id = ''", [])?;
+    tx.execute_batch(&format!("PRAGMA user_version = {};", SQLITE_SCHEMA_VERSION))?;
+    tx.commit()?;
+
+    Ok(())
+}
+
+fn column_exists(tx: &Transaction, table: &str, column: &str) -> Result<bool> {
+    let exists = tx.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info(?1) WHERE name = ?2",
+        params![table, column],
+        |row| row.get(0),
+    )?;
+
+    Ok(exists)
+}
+
+fn write_language(
+    tx: &Transaction,
+    language: LanguageCode,
+    packs: &[Pack],
+    products: &[ProductInfo],
+    cards: &[(&str, Vec<Card>)],
+) -> Result<usize> {
+    let language = language.to_path().to_string_lossy().to_string();
+
+    for table in SQLITE_TABLES {
+        let sql = format!("DELETE FROM {} WHERE language = ?1", table);
+        let deleted = tx.execute(&sql, params![language])?;
+        debug!("cleared {} rows of `{}` from {}", deleted, language, table);
+    }
+
+    let mut insert_pack = tx.prepare(
+        "INSERT INTO packs (language, id, raw_title, prefix, title, label, product_type,
+                            set_code, set_number, position)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+    )?;
+
+    for (position, pack) in packs.iter().enumerate() {
+        insert_pack.execute(params![
+            language,
+            pack.id,
+            pack.raw_title,
+            pack.title_parts.prefix,
+            pack.title_parts.title,
+            pack.title_parts.label,
+            enum_name(&pack.product_type)?,
+            pack.set_code,
+            pack.set_number,
+            position,
+        ])?;
+    }
+
+    let mut insert_product = tx.prepare(
+        "INSERT INTO products (language, pack_id, title, release_date, img_url, img_full_url,
+                               price, pack_size)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+    )?;
+
+    // Products pulled along with an older list of packs may refer to packs that are gone
+    for product in products
+        .iter()
+        .filter(|product| packs.iter().any(|pack| pack.id == product.pack_id))
+    {
+        insert_product.execute(params![
+            language,
+            product.pack_id,
+            product.title,
+            product.release_date.map(|date| date.to_string()),
+            product.img_url,
+            product.img_full_url,
+            product.price,
+            product.pack_size,
+        ])?;
+    }
+
+    // The same card can be listed in several packs (reprints), it is kept under the first one
+    let mut insert_card = tx.prepare(
+        "INSERT INTO cards (language, id, base_id, variant_kind, variant_index, pack_id, name,
+                            rarity, category, block, product, img_url, img_full_url,
+                            illustration, illustrator_name, cost, power, counter, effect,
+                            trigger)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17,
+                 ?18, ?19, ?20)
+         ON CONFLICT (language, id) DO NOTHING",
+    )?;
+    let mut insert_pack_card = tx.prepare(
+        "INSERT OR IGNORE INTO pack_cards (language, pack_id, card_id) VALUES (?1, ?2, ?3)",
+    )?;
+    let mut insert_color = tx.prepare(
+        "INSERT OR IGNORE INTO card_colors (language, card_id, color) VALUES (?1, ?2, ?3)",
+    )?;
+    let mut insert_attribute = tx.prepare(
+        "INSERT OR IGNORE INTO card_attributes (language, card_id, attribute) VALUES (?1, ?2, ?3)",
+    )?;
+    let mut insert_type = tx.prepare(
+        "INSERT OR IGNORE INTO card_types (language, card_id, position, type)
+         VALUES (?1, ?2, ?3, ?4)",
+    )?;
+
+    let mut card_count = 0;
+    for (pack_id, pack_cards) in cards {
+        for card in pack_cards {
+            let variant_kind = match card.variant {
+                Some(ref variant) => Some(enum_name(&variant.kind)?),
+                None => None,
+            };
+
+            let illustration = match card.illustration {
+                Some(ref illustration) => Some(enum_name(illustration)?),
+                None => None,
+            };
+
+            let inserted = insert_card.execute(params![
+                language,
+                card.id,
+                card.base_id,
+                variant_kind,
+                card.variant.as_ref().map(|v| v.index),
+                card.pack_id,
+                card.name,
+                enum_name(&card.rarity)?,
+                enum_name(&card.category)?,
+                card.block,
+                card.product,
+                card.img_url,
+                card.img_full_url,
+                illustration,
+                card.illustrator_name,
+                card.cost,
+                card.power,
+                card.counter,
+                card.effect,
+                card.trigger,
+            ])?;
+
+            insert_pack_card.execute(params![language, pack_id, card.id])?;
+            if inserted == 0 {
+                debug!(
+                    "card `{}` already exported, linked to `{}`",
+                    card.id, pack_id
+                );
+                continue;
+            }
+
+            card_count += 1;
+            for color in card.colors.iter() {
+                insert_color.execute(params![language, card.id, enum_name(color)?])?;
+            }
+
+            for attribute in card.attributes.iter() {
+                insert_attribute.execute(params![language, card.id, enum_name(attribute)?])?;
+            }
+
+            for (position, card_type) in card.types.iter().enumerate() {
+                insert_type.execute(params![language, card.id, position, card_type])?;
+            }
+        }
+    }
+
+    Ok(card_count)
+}
+
+/// Name of a unit enum variant as serialized in the JSON files, e.g. `SuperRare`.
+fn enum_name<T: Serialize>(value: &T) -> Result<String> {
+    let value = serde_json::to_value(value)?;
+    let name = value
+        .as_str()
+        .context("expected enum to serialize as a string")?
+        .to_string();
+
+    Ok(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::{test_util, CardColor, CardRarity};
+
+    fn get_test_pack(id: &str) -> Pack {
+        serde_json::from_str(&format!(
+            r#"{{"id":"{}","raw_title":"PREFIX -TITLE- [LABEL]",
+                "title_parts":{{"prefix":"PREFIX","title":"TITLE","label":"LABEL"}}}}"#,
+            id
+        ))
+        .unwrap()
+    }
+
+    fn get_test_card(id: &str, pack_id: &str) -> Card {
+        Card {
+            pack_id: pack_id.to_string(),
+            rarity: CardRarity::SuperRare,
+            colors: vec![CardColor::Red, CardColor::Blue],
+            attributes: Vec::new(),
+            types: vec![String::from("Straw Hat Crew"), String::from("East Blue")],
+            ..test_util::card(id)
+        }
+    }
+
+    fn get_test_conn() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate_schema(&mut conn).unwrap();
+        conn
+    }
+
+    fn count(conn: &Connection, sql: &str) -> i64 {
+        conn.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn enum_name_returns_variant_name() {
+        assert_eq!(enum_name(&CardRarity::SuperRare).unwrap(), "SuperRare");
+    }
+
+    #[test]
+    fn write_language_normalizes_cards() {
+        let mut conn = get_test_conn();
+        let packs = vec![get_test_pack("1")];
+        let cards = vec![("1", vec![get_test_card("OP01-016", "1")])];
+
+        let tx = conn.transaction().unwrap();
+        write_language(&tx, LanguageCode::English, &packs, &[], &cards).unwrap();
+        tx.commit().unwrap();
+
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM cards"), 1);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM card_colors"), 2);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM card_types"), 2);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM card_attributes"), 0);
+        assert_eq!(
+            count(
+                &conn,
+                "SELECT COUNT(*) FROM cards WHERE rarity = 'SuperRare' AND cost = 1"
+            ),
+            1
+        );
+    }
+
+    #[test]
+    fn write_language_exports_pack_product_type() {
+        let mut conn = get_test_conn();
+        let packs = vec![
+            serde_json::from_str(
+                r#"{"id":"569101","raw_title":"BOOSTER PACK -ROMANCE DAWN- [OP01]",
+                    "title_parts":{"prefix":"BOOSTER PACK","title":"ROMANCE DAWN","label":"OP01"},
+                    "product_type":"Booster","set_code":"OP-01","set_number":1}"#,
+            )
+            .unwrap(),
+            get_test_pack("1"),
+        ];
+
+        let tx = conn.transaction().unwrap();
+        write_language(&tx, LanguageCode::English, &packs, &[], &[]).unwrap();
+        tx.commit().unwrap();
+
+        assert_eq!(
+            count(
+                &conn,
+                "SELECT COUNT(*) FROM packs
+                 WHERE product_type = 'Booster' AND set_code = 'OP-01' AND set_number = 1"
+            ),
+            1
+        );
+        assert_eq!(
+            count(
+                &conn,
+                "SELECT COUNT(*) FROM packs WHERE product_type = 'Other'"
+            ),
+            1
+        );
+    }
+
+    #[test]
+    fn write_language_links_reprints_to_every_pack() {
+        let mut conn = get_test_conn();
+        let packs = vec![get_test_pack("1"), get_test_pack("2")];
+        let cards = vec![
+            ("1", vec![get_test_card("OP01-016", "1")]),
+            ("2", vec![get_test_card("OP01-016", "2")]),
+        ];
+
+        let tx = conn.transaction().unwrap();
+        let card_count = write_language(&tx, LanguageCode::English, &packs, &[], &cards).unwrap();
+        tx.commit().unwrap();
+
+        assert_eq!(card_count, 1);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM pack_cards"), 2);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM card_colors"), 2);
+    }
+
+    #[test]
+    fn write_language_skips_products_of_unknown_packs() {
+        let mut conn = get_test_conn();
+        let packs = vec![get_test_pack("1")];
+        let cards = vec![("1", vec![get_test_card("OP01-016", "1")])];
+        let product = |pack_id: &str| ProductInfo {
+            pack_id: pack_id.to_string(),
+            title: String::from("PREFIX -TITLE- [LABEL]"),
+            release_date: chrono::NaiveDate::from_ymd_opt(2022, 12, 2),
+            img_url: None,
+            img_full_url: None,
+            price: Some(String::from("$3.99")),
+            pack_size: None,
+        };
+
+        let tx = conn.transaction().unwrap();
+        let products = [product("1"), product("2")];
+        write_language(&tx, LanguageCode::English, &packs, &products, &cards).unwrap();
+        tx.commit().unwrap();
+
+        assert_eq!(
+            count(
+                &conn,
+                "SELECT COUNT(*) FROM products WHERE release_date = '2022-12-02'"
+            ),
+            1
+        );
+    }
+
+    #[test]
+    fn write_language_keeps_other_languages() {
+        let mut conn = get_test_conn();
+        let packs = vec![get_test_pack("1")];
+        let cards = vec![("1", vec![get_test_card("OP01-016", "1")])];
+
+        for language in [
+            LanguageCode::English,
+            LanguageCode::Japanese,
+            LanguageCode::English,
+        ] {
+            let tx = conn.transaction().unwrap();
+            write_language(&tx, language, &packs, &[], &cards).unwrap();
+            tx.commit().unwrap();
+        }
+
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM packs"), 2);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM cards"), 2);
+        assert_eq!(
+            count(
+                &conn,
+                "SELECT COUNT(*) FROM cards WHERE language = 'japanese'"
+            ),
+            1
+        );
+    }
+
+    /// Tables as created by the first version of the export, before the schema was versioned.
+    const UNVERSIONED_SCHEMA: &str = "
+        CREATE TABLE packs (
+            language TEXT NOT NULL,
+            id TEXT NOT NULL,
+            raw_title TEXT NOT NULL,
+            prefix TEXT,
+            title TEXT NOT NULL,
+            label TEXT,
+            position INTEGER NOT NULL,
+            PRIMARY KEY (language, id)
+        );
+
+        CREATE TABLE cards (
+            language TEXT NOT NULL,
+            id TEXT NOT NULL,
+            pack_id TEXT NOT NULL,
+            name TEXT NOT NULL,
+            rarity TEXT NOT NULL,
+            category TEXT NOT NULL,
+            img_url TEXT NOT NULL,
+            img_full_url TEXT,
+            cost INTEGER,
+            power INTEGER,
+            counter INTEGER,
+            effect TEXT NOT NULL,
+            trigger TEXT,
+            PRIMARY KEY (language, id),
+            FOREIGN KEY (language, pack_id) REFERENCES packs (language, id)
+        );
+
+        INSERT INTO packs VALUES ('japanese', '1', 'PREFIX -TITLE- [LABEL]', NULL, 'TITLE', NULL, 0);
+        INSERT INTO cards VALUES ('japanese', 'OP01-016', '1', 'Nami', 'Rare', 'Character',
+                                  '../images/cardlist/card/OP01-016.png', NULL, 1, 2000, 1000,
+                                  '', NULL);
+    ";
+
+    #[test]
+    fn migrate_schema_upgrades_unversioned_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(UNVERSIONED_SCHEMA).unwrap();
+
+        migrate_schema(&mut conn).unwrap();
+        let packs = vec![get_test_pack("1")];
+        let cards = vec![("1", vec![get_test_card("OP01-016", "1")])];
+        let products = [ProductInfo {
+            pack_id: String::from("1"),
+            title: String::from("PREFIX -TITLE- [LABEL]"),
+            release_date: None,
+            img_url: None,
+            img_full_url: None,
+            price: None,
+            pack_size: None,
+        }];
+
+        let tx = conn.transaction().unwrap();
+        write_language(&tx, LanguageCode::English, &packs, &products, &cards).unwrap();
+        tx.commit().unwrap();
+
+        assert_eq!(count(&conn, "PRAGMA user_version"), SQLITE_SCHEMA_VERSION);
+        assert_eq!(count(&conn, "SELECT COUNT(*) FROM products"), 1);
+        assert_eq!(
+            count(
+                &conn,
+                "SELECT COUNT(*) FROM cards WHERE language = 'japanese' AND base_id = 'OP01-016'"
+            ),
+            1
+        );
+        assert_eq!(
+            count(
+                &conn,
+                "SELECT COUNT(*) FROM packs WHERE language = 'japanese' AND product_type = 'Other'"
+            ),
+            1
+        );
+    }
+
+    #[test]
+    fn migrate_schema_renames_release_order() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(&SQLITE_SCHEMA.replace("set_number", "release_order"))
+            .unwrap();
+        conn.execute_batch(
+            "PRAGMA user_version = 1;
+             INSERT INTO packs (language, id, raw_title, title, product_type, release_order,
+                                position)
+             VALUES ('english', '569107', 'BOOSTER PACK -500 YEARS IN THE FUTURE- [OP-07]',
+                     '500 YEARS IN THE FUTURE', 'Booster', 7, 0);",
+        )
+        .unwrap();
+
+        migrate_schema(&mut conn).unwrap();
+
+        assert_eq!(count(&conn, "PRAGMA user_version"), SQLITE_SCHEMA_VERSION);
+        assert_eq!(
+            count(&conn, "SELECT COUNT(*) FROM packs WHERE set_number = 7"),
+            1
+        );
+    }
+
+    #[test]
+    fn migrate_schema_is_idempotent() {
+        let mut conn = get_test_conn();
+        migrate_schema(&mut conn).unwrap();
+
+        assert_eq!(count(&conn, "PRAGMA user_version"), SQLITE_SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn migrate_schema_newer_version_returns_err() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(&format!(
+            "PRAGMA user_version = {};",
+            SQLITE_SCHEMA_VERSION + 1
+        ))
+        .unwrap();
+
+        assert!(migrate_schema(&mut conn).is_err());
+    }
+}
@@ -0,0 +1,310 @@
+use anyhow::{bail, ensure, Context, Result};
+use log::{debug, trace};
+use reqwest::blocking::Client;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::error::VegaError;
+
+/// Raw result of an HTTP GET, independent from the transport that produced it.
+#[derive(Clone, Debug, Default)]
+pub struct FetchResponse {
+    pub status: u16,
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl FetchResponse {
+    pub fn new(status: u16, content_type: &str, body: Vec<u8>) -> Self {
+        let mut headers = HashMap::new();
+        headers.insert("content-type".to_string(), content_type.to_string());
+
+        FetchResponse {
+            status,
+            headers,
+            body,
+        }
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .get(&name.to_lowercase())
+            .map(|value| value.as_str())
+    }
+
+    pub fn content_type(&self) -> Option<&str> {
+        self.header("content-type")
+    }
+
+    pub fn text(self) -> Result<String> {
+        let text = String::from_utf8(self.body).context("response body is not valid UTF-8")?;
+        Ok(text)
+    }
+}
+
+pub trait Fetcher: Send + Sync {
+    fn get(&self, url: &str, query: &[(&str, &str)]) -> Result<FetchResponse>;
+}
+
+impl<F: Fetcher + ?Sized> Fetcher for &F {
+    fn get(&self, url: &str, query: &[(&str, &str)]) -> Result<FetchResponse> {
+        (**self).get(url, query)
+    }
+}
+
+impl<F: Fetcher + ?Sized> Fetcher for Box<F> {
+    fn get(&self, url: &str, query: &[(&str, &str)]) -> Result<FetchResponse> {
+        (**self).get(url, query)
+    }
+}
+
+/// Returns the fixtures directory fetcher when a directory is given, the live one otherwise.
+pub fn create_fetcher(fixtures_dir: Option<&Path>) -> Result<Box<dyn Fetcher>> {
+    match fixtures_dir {
+        Some(dir) => Ok(Box::new(FixtureFetcher::new(dir)?)),
+        None => Ok(Box::new(LiveFetcher::new())),
+    }
+}
+
+/// Key under which a request is stored by the offline fetchers.
+///
+/// `https://host/cardlist?series=569101` becomes `cardlist__series=569101.html` and
+/// `https://host/images/card/OP01-001.png?240322` becomes `images/card/OP01-001.png`.
+pub fn fixture_key(url: &str, query: &[(&str, &str)]) -> String {
+    let path = match url.find("://") {
+        Some(scheme_end) => {
+            let without_scheme = &url[scheme_end + 3..];
+            match without_scheme.find('/') {
+                Some(slash_pos) => &without_scheme[slash_pos + 1..],
+                None => "",
+            }
+        }
+        None => url,
+    };
+
+    let path = match path.find('?') {
+        Some(quest_mark_pos) => &path[..quest_mark_pos],
+        None => path,
+    };
+
+    let mut key = path.trim_matches('/').to_string();
+    if key.is_empty() {
+        key = String::from("index");
+    }
+
+    let has_extension = key.rsplit('/').next().unwrap_or_default().contains('.');
+
+    for (name, value) in query {
+        key.push_str(&format!("__{}={}", name, value));
+    }
+
+    if !has_extension || !query.is_empty() {
+        key.push_str(".html");
+    }
+
+    trace!("fixture key for `{}` {:?}: {}", url, query, key);
+    key
+}
+
+fn guess_content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "html" | "htm" => "text/html; charset=UTF-8",
+        "json" => "application/json",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "webp" => "image/webp",
+        "gif" => "image/gif",
+        _ => "application/octet-stream",
+    }
+}
+
+pub struct LiveFetcher {
+    client: Client,
+}
+
+impl LiveFetcher {
+    pub fn new() -> Self {
+        LiveFetcher {
+            client: Client::new(),
+        }
+    }
+}
+
+impl Default for LiveFetcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Fetcher for LiveFetcher {
+    fn get(&self, url: &str, query: &[(&str, &str)]) -> Result<FetchResponse> {
+        debug!("GET `{}` {:?}", url, query);
+        let network_error = |source| VegaError::Network {
+            url: url.to_string(),
+            source,
+        };
+
+        let response = self
+            .client
+            .get(url)
+            .query(query)
+            .send()
+            .map_err(network_error)?;
+
+        let status = response.status().as_u16();
+        let headers = response
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| {
+                let value = value.to_str().ok()?;
+                Some((name.as_str().to_lowercase(), value.to_string()))
+            })
+            .collect();
+
+        let body = response.bytes().map_err(network_error)?.to_vec();
+        Ok(FetchResponse {
+            status,
+            headers,
+            body,
+        })
+    }
+}
+
+/// Serves requests from files saved on disk, see [`fixture_key`] for the layout.
+pub struct FixtureFetcher {
+    root_dir: PathBuf,
+}
+
+impl FixtureFetcher {
+    pub fn new(root_dir: &Path) -> Result<Self> {
+        ensure!(
+            root_dir.is_dir(),
+            format!("fixtures directory not found: {}", root_dir.display())
+        );
+
+        Ok(FixtureFetcher {
+            root_dir: root_dir.to_path_buf(),
+        })
+    }
+
+    pub fn fixture_path(&self, url: &str, query: &[(&str, &str)]) -> PathBuf {
+        self.root_dir.join(fixture_key(url, query))
+    }
+}
+
+impl Fetcher for FixtureFetcher {
+    fn get(&self, url: &str, query: &[(&str, &str)]) -> Result<FetchResponse> {
+        let path = self.fixture_path(url, query);
+        debug!(
+            "GET `{}` {:?} from fixture `{}`",
+            url,
+            query,
+            path.display()
+        );
+
+        if !path.exists() {
+            bail!("fixture not found for `{}`: {}", url, path.display());
+        }
+
+        let body =
+            fs::read(&path).with_context(|| format!("Failed to open file: {}", path.display()))?;
+
+        Ok(FetchResponse::new(200, guess_content_type(&path), body))
+    }
+}
+
+/// Serves canned responses kept in memory, keyed by [`fixture_key`].
+#[cfg(test)]
+#[derive(Default)]
+pub struct MemoryFetcher {
+    responses: HashMap<String, FetchResponse>,
+}
+
+#[cfg(test)]
+impl MemoryFetcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, url: &str, query: &[(&str, &str)], response: FetchResponse) {
+        self.responses.insert(fixture_key(url, query), response);
+    }
+
+    pub fn insert_html(&mut self, url: &str, query: &[(&str, &str)], html: &str) {
+        let response = FetchResponse::new(200, "text/html; charset=UTF-8", html.into());
+        self.insert(url, query, response);
+    }
+}
+
+#[cfg(test)]
+impl Fetcher for MemoryFetcher {
+    fn get(&self, url: &str, query: &[(&str, &str)]) -> Result<FetchResponse> {
+        let key = fixture_key(url, query);
+        debug!("GET `{}` {:?} from memory ({})", url, query, key);
+
+        match self.responses.get(&key) {
+            Some(response) => Ok(response.clone()),
+            None => bail!("no canned response for `{}` ({})", url, key),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixture_key_cardlist_without_query() {
+        let key = fixture_key("https://en.onepiece-cardgame.com/cardlist", &[]);
+        assert_eq!(key, "cardlist.html");
+    }
+
+    #[test]
+    fn fixture_key_cardlist_with_query() {
+        let key = fixture_key(
+            "https://en.onepiece-cardgame.com/cardlist",
+            &[("series", "569101")],
+        );
+        assert_eq!(key, "cardlist__series=569101.html");
+    }
+
+    #[test]
+    fn fixture_key_image_strips_query_string() {
+        let key = fixture_key(
+            "https://en.onepiece-cardgame.com/images/cardlist/card/OP01-001.png?240322",
+            &[],
+        );
+        assert_eq!(key, "images/cardlist/card/OP01-001.png");
+    }
+
+    #[test]
+    fn fixture_key_root_is_index() {
+        let key = fixture_key("https://en.onepiece-cardgame.com/", &[]);
+        assert_eq!(key, "index.html");
+    }
+
+    #[test]
+    fn memory_fetcher_returns_canned_response() {
+        let mut fetcher = MemoryFetcher::new();
+        fetcher.insert_html("https://host/cardlist", &[], "<html></html>");
+
+        let response = fetcher.get("https://host/cardlist", &[]).unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.content_type(), Some("text/html; charset=UTF-8"));
+        assert_eq!(response.text().unwrap(), "<html></html>");
+    }
+
+    #[test]
+    fn memory_fetcher_unknown_url_returns_err() {
+        let fetcher = MemoryFetcher::new();
+        assert!(fetcher.get("https://host/cardlist", &[]).is_err());
+    }
+}
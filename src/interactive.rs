@@ -2,17 +2,26 @@ use std::{
     fs,
     io::{self, Write},
     path::{Path, PathBuf},
-    time::Instant,
 };
 
 use anyhow::{bail, Result};
 use clap::ValueEnum;
-use log::{error, info};
+use log::info;
 use yansi::Paint;
 
-use crate::{cli::LanguageCode, localizer::Localizer, scraper::OpTcgScraper, storage::DataStore};
+use crate::{
+    cli::LanguageCode,
+    fetcher::Fetcher,
+    policy::RequestOverrides,
+    pull::{self, PullOptions},
+};
 
-pub fn show_interactive(config_dir: &Path) -> Result<()> {
+pub fn show_interactive(
+    config_dir: &Path,
+    fetcher: &dyn Fetcher,
+    request: RequestOverrides,
+    lenient: bool,
+) -> Result<()> {
     println!("{}", "+---------------------------+".yellow());
     println!(
         "{} {} {}",
@@ -45,6 +54,7 @@ pub fn show_interactive(config_dir: &Path) -> Result<()> {
     info!("value to use: {}", value);
 
     let data_dir = PathBuf::from(&value);
+    let mut sync = false;
     if data_dir.exists() {
         info!(
             "directory `{}` exists, prompting user for removal",
@@ -68,8 +78,24 @@ pub fn show_interactive(config_dir: &Path) -> Result<()> {
             fs::remove_dir_all(&data_dir)?;
             info!("removed directory: {}", data_dir.display());
         } else {
-            info!("user cancelled directory removal: {}", data_dir.display());
-            bail!("Aborted, directory has been kept: `{}`", data_dir.display());
+            info!("user declined directory removal, prompting for sync");
+            let prompt = format!(
+                "Sync changes into it instead? ({}/{}): ",
+                "y".green(),
+                "N".red()
+            );
+            let input = input_prompt(&prompt)?;
+            info!("user input: {}", input);
+
+            let value = if input.is_empty() { "no" } else { &input };
+            info!("value to use: {}", value);
+
+            if !is_yes(value) {
+                info!("user cancelled directory removal: {}", data_dir.display());
+                bail!("Aborted, directory has been kept: `{}`", data_dir.display());
+            }
+
+            sync = true;
         }
     }
 
@@ -81,63 +107,15 @@ pub fn show_interactive(config_dir: &Path) -> Result<()> {
     let value = if input.is_empty() { "no" } else { &input };
     info!("value to use: {}", value);
 
-    let download_images = is_yes(value);
-
-    let localizer = Localizer::load(config_dir, language)?;
-    let scraper = OpTcgScraper::new(&localizer);
-    let store = DataStore::new(&data_dir, language);
-
-    println!("\nFetching packs...");
-    let start = Instant::now();
-
-    let packs = scraper.fetch_all_packs()?;
-    store.write_packs(&packs)?;
-
-    let duration = start.elapsed();
-    info!("fetching packs took: {:?}", duration);
-
-    println!("Successfully stored data for {} packs!\n", packs.len());
-
-    let start = Instant::now();
-    for (idx, pack) in packs.iter().enumerate() {
-        print!(
-            "[{}/{}] Fetching cards for pack `{}`...",
-            (idx + 1),
-            packs.len(),
-            pack.id
-        );
-        io::stdout().flush()?;
-
-        let cards = scraper.fetch_all_cards(&pack.id)?;
-        if cards.is_empty() {
-            error!("no cards available for pack `{}`", &pack.id);
-            bail!("No cards found");
-        }
-
-        store.write_cards(&pack.id, &cards)?;
-        info!("fetched and wrote cards for: `{}`", pack.id);
-        println!(" OK");
-
-        if download_images {
-            println!("Downloading images for pack `{}`...", pack.id);
-            for (idx, card) in cards.iter().enumerate() {
-                print!(
-                    "[{}/{}] Downloading image for card `{}`...",
-                    idx + 1,
-                    cards.len(),
-                    card.id
-                );
-                io::stdout().flush()?;
-
-                let img_data = scraper.download_card_image(card)?;
-                store.write_image(card, img_data)?;
-                println!(" OK");
-            }
-        }
-    }
+    let options = PullOptions {
+        download_images: is_yes(value),
+        sync,
+        jobs: 1,
+        request,
+        lenient,
+    };
 
-    let duration = start.elapsed();
-    info!("fetching cards (and images) took: {:?}", duration);
+    pull::pull_all(config_dir, fetcher, language, &data_dir, options)?;
 
     println!("Final data is available in: {}", data_dir.display());
 
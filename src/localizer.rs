@@ -1,116 +1,269 @@
-use anyhow::{ensure, Context, Result};
+use anyhow::{bail, Context, Result};
+use directories::ProjectDirs;
 use log::{debug, info};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, fs, path::Path};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+use toml::{Table, Value};
 
-use crate::cli::LanguageCode;
+use crate::{cli::LanguageCode, error::VegaError, policy::RequestPolicy, selectors::Selectors};
+
+/// Locale files compiled into the binary, overridden per key by files in the config directory.
+const BUILTIN_LOCALES: [(&str, &str); 7] = [
+    ("en", include_str!("../config/en.toml")),
+    ("en_asia", include_str!("../config/en_asia.toml")),
+    ("jp", include_str!("../config/jp.toml")),
+    ("th", include_str!("../config/th.toml")),
+    ("zh_cn", include_str!("../config/zh_cn.toml")),
+    ("zh_hk", include_str!("../config/zh_hk.toml")),
+    ("zh_tw", include_str!("../config/zh_tw.toml")),
+];
+
+/// Label shown by the site for a key, either a single string or every accepted spelling.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum Labels {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Labels {
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        let labels = match self {
+            Labels::One(label) => std::slice::from_ref(label),
+            Labels::Many(labels) => labels.as_slice(),
+        };
+
+        labels.iter().map(String::as_str)
+    }
+}
+
+pub type LabelMap = HashMap<String, Labels>;
+
+/// Label to key lookups, built once when the locale is loaded.
+#[derive(Debug, Default)]
+struct ReverseIndex {
+    colors: HashMap<String, String>,
+    attributes: HashMap<String, String>,
+    categories: HashMap<String, String>,
+    rarities: HashMap<String, String>,
+    illustrations: HashMap<String, String>,
+    effects: HashMap<String, String>,
+}
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Localizer {
     pub hostname: String,
 
-    pub colors: HashMap<String, String>,
-    pub attributes: HashMap<String, String>,
-    pub categories: HashMap<String, String>,
-    pub rarities: HashMap<String, String>,
+    pub colors: LabelMap,
+    pub attributes: LabelMap,
+    pub categories: LabelMap,
+    pub rarities: LabelMap,
+    #[serde(default)]
+    pub illustrations: LabelMap,
+    #[serde(default)]
+    pub effects: LabelMap,
+
+    #[serde(default)]
+    pub request: RequestPolicy,
+    #[serde(default)]
+    pub selectors: Selectors,
+
+    #[serde(skip)]
+    index: ReverseIndex,
 }
 
 impl Localizer {
-    fn reverse_search(hash_map: &HashMap<String, String>, value: &str) -> Option<String> {
-        hash_map.iter().find_map(|(key, val)| {
-            if val == value {
-                Some(key.to_string())
-            } else {
-                None
+    fn reverse_search(index: &HashMap<String, String>, value: &str) -> Option<String> {
+        index.get(value).cloned()
+    }
+
+    /// Maps every label of `map` to its key, failing if a label is used by several keys.
+    fn build_index(section: &str, map: &LabelMap) -> Result<HashMap<String, String>> {
+        // Sorted so the error reported for an ambiguous config is always the same
+        let mut keys: Vec<&String> = map.keys().collect();
+        keys.sort();
+
+        let mut index: HashMap<String, String> = HashMap::new();
+        for key in keys {
+            for label in map[key].iter() {
+                match index.get(label) {
+                    Some(other_key) if other_key != key => bail!(
+                        "ambiguous [{}] label `{}`, used by both `{}` and `{}`",
+                        section,
+                        label,
+                        other_key,
+                        key
+                    ),
+                    _ => {
+                        index.insert(label.to_string(), key.to_string());
+                    }
+                }
             }
-        })
+        }
+
+        Ok(index)
+    }
+
+    fn build_indexes(&mut self) -> Result<()> {
+        self.index = ReverseIndex {
+            colors: Self::build_index("colors", &self.colors)?,
+            attributes: Self::build_index("attributes", &self.attributes)?,
+            categories: Self::build_index("categories", &self.categories)?,
+            rarities: Self::build_index("rarities", &self.rarities)?,
+            illustrations: Self::build_index("illustrations", &self.illustrations)?,
+            effects: Self::build_index("effects", &self.effects)?,
+        };
+
+        Ok(())
     }
 
     pub fn match_color(&self, value: &str) -> Option<String> {
-        Self::reverse_search(&self.colors, value)
+        Self::reverse_search(&self.index.colors, value)
     }
 
     pub fn match_attribute(&self, value: &str) -> Option<String> {
-        Self::reverse_search(&self.attributes, value)
+        Self::reverse_search(&self.index.attributes, value)
     }
 
     pub fn match_category(&self, value: &str) -> Option<String> {
-        Self::reverse_search(&self.categories, value)
+        Self::reverse_search(&self.index.categories, value)
     }
 
     pub fn match_rarity(&self, value: &str) -> Option<String> {
-        Self::reverse_search(&self.rarities, value)
+        Self::reverse_search(&self.index.rarities, value)
     }
 
-    pub fn find_locales(config_dir: &Path) -> Result<()> {
-        ensure!(
-            config_dir.exists(),
-            format!("config directory not found: {}", config_dir.display())
-        );
-
-        let entries = fs::read_dir(config_dir)?;
-        println!("config directory: {}", config_dir.display());
+    pub fn match_illustration(&self, value: &str) -> Option<String> {
+        Self::reverse_search(&self.index.illustrations, value)
+    }
 
-        for entry in entries {
-            let entry = entry?;
-            let file_name = entry.file_name();
-            println!("- {}", file_name.to_string_lossy());
-        }
+    pub fn match_effect(&self, value: &str) -> Option<String> {
+        Self::reverse_search(&self.index.effects, value)
+    }
 
-        Ok(())
+    /// Per-user directory where locale overrides are looked up when `--config-dir` is not given.
+    pub fn default_config_dir() -> Option<PathBuf> {
+        ProjectDirs::from("", "", "vegapull").map(|dirs| dirs.config_dir().to_path_buf())
     }
 
-    pub fn load(config_dir: &Path, language: LanguageCode) -> Result<Localizer> {
+    pub fn locale_name(language: LanguageCode) -> &'static str {
         match language {
-            LanguageCode::ChineseHongKong => Self::load_from_file(config_dir, "zh_hk"),
-            LanguageCode::ChineseSimplified => Self::load_from_file(config_dir, "zh_cn"),
-            LanguageCode::ChineseTaiwan => Self::load_from_file(config_dir, "zh_tw"),
-            LanguageCode::English => Self::load_from_file(config_dir, "en"),
-            LanguageCode::EnglishAsia => Self::load_from_file(config_dir, "en_asia"),
-            LanguageCode::Japanese => Self::load_from_file(config_dir, "jp"),
-            LanguageCode::Thai => Self::load_from_file(config_dir, "th"),
+            LanguageCode::ChineseHongKong => "zh_hk",
+            LanguageCode::ChineseSimplified => "zh_cn",
+            LanguageCode::ChineseTaiwan => "zh_tw",
+            LanguageCode::English => "en",
+            LanguageCode::EnglishAsia => "en_asia",
+            LanguageCode::Japanese => "jp",
+            LanguageCode::Thai => "th",
         }
     }
 
+    pub fn load(config_dir: &Path, language: LanguageCode) -> Result<Localizer> {
+        Self::load_from_file(config_dir, Self::locale_name(language))
+    }
+
+    /// Loads the built-in locale, with any key set in `<config_dir>/<locale>.toml` replacing
+    /// the built-in value.
     pub fn load_from_file(config_dir: &Path, locale: &str) -> Result<Localizer> {
-        ensure!(
-            config_dir.exists(),
-            format!("config directory not found: {}", config_dir.display())
-        );
+        let config_error = |reason: String| VegaError::Config {
+            locale: locale.to_string(),
+            reason,
+        };
+
+        let (locale_table, _) =
+            Self::load_table(config_dir, locale).map_err(|e| config_error(format!("{:#}", e)))?;
+
+        let mut localizer =
+            Localizer::deserialize(locale_table).map_err(|e| config_error(e.to_string()))?;
+        localizer
+            .build_indexes()
+            .map_err(|e| config_error(format!("{:#}", e)))?;
+        localizer
+            .selectors
+            .validate()
+            .map_err(|e| config_error(format!("{:#}", e)))?;
+
+        Ok(localizer)
+    }
+
+    /// Built-in locale merged with its override file, along with the path of that file when
+    /// there is one.
+    pub fn load_table(config_dir: &Path, locale: &str) -> Result<(Table, Option<PathBuf>)> {
+        let (_, builtin_data) = BUILTIN_LOCALES
+            .iter()
+            .find(|(name, _)| *name == locale)
+            .with_context(|| format!("unknown locale: {}", locale))?;
+
+        let mut locale_table: Table = toml::from_str(builtin_data)
+            .with_context(|| format!("Failed to parse built-in locale: {}", locale))?;
 
         let locale_path = config_dir.join(format!("{}.toml", locale));
-        ensure!(
-            locale_path.exists(),
-            format!("locale file not found: {}", locale_path.display())
-        );
+        if !locale_path.exists() {
+            info!("using built-in {} locale", locale);
+            return Ok((locale_table, None));
+        }
 
-        info!("load {} locale from: {}", locale, locale_path.display());
+        info!(
+            "load {} locale overrides from: {}",
+            locale,
+            locale_path.display()
+        );
 
         let locale_data = fs::read_to_string(&locale_path)
             .with_context(|| format!("Failed to open file: {}", locale_path.display()))?;
         debug!("loaded {}", locale_data);
 
-        let localizer: Localizer = toml::from_str(&locale_data)?;
-        Ok(localizer)
+        let overrides: Table = toml::from_str(&locale_data)
+            .with_context(|| format!("Failed to parse file: {}", locale_path.display()))?;
+        Self::merge_tables(&mut locale_table, overrides);
+
+        Ok((locale_table, Some(locale_path)))
+    }
+
+    pub fn builtin_locales() -> impl Iterator<Item = &'static str> {
+        BUILTIN_LOCALES.iter().map(|(name, _)| *name)
+    }
+
+    fn merge_tables(base: &mut Table, overrides: Table) {
+        for (key, value) in overrides {
+            match (base.get_mut(&key), value) {
+                (Some(Value::Table(base_table)), Value::Table(table)) => {
+                    Self::merge_tables(base_table, table)
+                }
+                (_, value) => {
+                    base.insert(key, value);
+                }
+            }
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::card::{
+        effect::{CardKeyword, CardTiming},
+        CardAttribute, CardCategory, CardColor, CardIllustration, CardRarity,
+    };
+    use clap::ValueEnum;
+    use std::collections::BTreeSet;
 
-    fn get_test_map() -> HashMap<String, String> {
+    fn get_test_map() -> LabelMap {
         let mut map = HashMap::new();
-        map.insert(String::from("foo"), String::from("Toto"));
-        map.insert(String::from("bar"), String::from("Tata"));
-        map.insert(String::from("baz"), String::from("Tutu"));
+        map.insert(String::from("foo"), Labels::One(String::from("Toto")));
+        map.insert(String::from("bar"), Labels::One(String::from("Tata")));
+        map.insert(String::from("baz"), Labels::One(String::from("Tutu")));
 
         map
     }
 
     #[test]
     fn reverse_search_returns_some() {
-        let map = get_test_map();
+        let map = Localizer::build_index("test", &get_test_map()).unwrap();
 
         let actual = Localizer::reverse_search(&map, "Toto");
         let expected = Some(String::from("foo"));
@@ -120,11 +273,218 @@ mod tests {
 
     #[test]
     fn reverse_search_returns_none() {
-        let map = get_test_map();
+        let map = Localizer::build_index("test", &get_test_map()).unwrap();
 
         let actual = Localizer::reverse_search(&map, "Titi");
         let expected = None;
 
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn reverse_search_any_spelling_returns_some() {
+        let mut map = get_test_map();
+        map.insert(
+            String::from("qux"),
+            Labels::Many(vec![String::from("Titi"), String::from("TT")]),
+        );
+        let map = Localizer::build_index("test", &map).unwrap();
+
+        assert_eq!(
+            Localizer::reverse_search(&map, "Titi"),
+            Some(String::from("qux"))
+        );
+        assert_eq!(
+            Localizer::reverse_search(&map, "TT"),
+            Some(String::from("qux"))
+        );
+    }
+
+    #[test]
+    fn build_index_ambiguous_label_returns_err() {
+        let mut map = get_test_map();
+        map.insert(
+            String::from("qux"),
+            Labels::Many(vec![String::from("Titi"), String::from("Toto")]),
+        );
+
+        let err = Localizer::build_index("test", &map).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "ambiguous [test] label `Toto`, used by both `foo` and `qux`"
+        );
+    }
+
+    #[test]
+    fn build_index_repeated_label_for_same_key_returns_ok() {
+        let mut map = get_test_map();
+        map.insert(
+            String::from("qux"),
+            Labels::Many(vec![String::from("Titi"), String::from("Titi")]),
+        );
+
+        assert!(Localizer::build_index("test", &map).is_ok());
+    }
+
+    #[test]
+    fn load_ambiguous_override_returns_err() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+        fs::write(dir.join("en.toml"), "[colors]\nred = \"Green\"\n").unwrap();
+
+        let result = Localizer::load(dir, LanguageCode::English);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn load_selector_override_keeps_other_defaults() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+        fs::write(dir.join("en.toml"), "[selectors]\nname = \"dt>h2\"\n").unwrap();
+
+        let result = Localizer::load(dir, LanguageCode::English);
+
+        let selectors = result.unwrap().selectors;
+        assert_eq!(selectors.name, "dt>h2");
+        assert_eq!(selectors.power, Selectors::default().power);
+    }
+
+    #[test]
+    fn load_invalid_selector_override_returns_err() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+        fs::write(dir.join("en.toml"), "[selectors]\nname = \"dt>>\"\n").unwrap();
+
+        let result = Localizer::load(dir, LanguageCode::English);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn load_english_accepts_every_special_spelling() {
+        let localizer = Localizer::load(Path::new("config"), LanguageCode::English).unwrap();
+
+        assert_eq!(
+            localizer.match_rarity("SP CARD"),
+            Some(String::from("special"))
+        );
+        assert_eq!(localizer.match_rarity("SP"), Some(String::from("special")));
+    }
+
+    fn keys(map: &LabelMap) -> BTreeSet<&str> {
+        map.keys().map(String::as_str).collect()
+    }
+
+    #[test]
+    fn load_every_language_returns_ok() {
+        for language in LanguageCode::value_variants() {
+            let localizer = Localizer::load(Path::new("config"), *language);
+            assert!(localizer.is_ok(), "{:?}: {:?}", language, localizer.err());
+        }
+    }
+
+    #[test]
+    fn load_every_language_has_same_keys() {
+        let reference = Localizer::load(Path::new("config"), LanguageCode::English).unwrap();
+
+        for language in LanguageCode::value_variants() {
+            let localizer = Localizer::load(Path::new("config"), *language).unwrap();
+
+            assert_eq!(keys(&localizer.colors), keys(&reference.colors));
+            assert_eq!(keys(&localizer.attributes), keys(&reference.attributes));
+            assert_eq!(keys(&localizer.categories), keys(&reference.categories));
+            assert_eq!(keys(&localizer.rarities), keys(&reference.rarities));
+            assert_eq!(
+                keys(&localizer.illustrations),
+                keys(&reference.illustrations)
+            );
+            assert_eq!(keys(&localizer.effects), keys(&reference.effects));
+        }
+    }
+
+    #[test]
+    fn load_english_keys_are_supported() {
+        let localizer = Localizer::load(Path::new("config"), LanguageCode::English).unwrap();
+
+        for key in localizer.colors.keys() {
+            assert!(CardColor::from_str(key).is_ok(), "color `{}`", key);
+        }
+
+        for key in localizer.attributes.keys() {
+            assert!(CardAttribute::from_str(key).is_ok(), "attribute `{}`", key);
+        }
+
+        for key in localizer.categories.keys() {
+            assert!(CardCategory::from_str(key).is_ok(), "category `{}`", key);
+        }
+
+        for key in localizer.rarities.keys() {
+            assert!(CardRarity::from_str(key).is_ok(), "rarity `{}`", key);
+        }
+
+        for key in localizer.illustrations.keys() {
+            assert!(
+                CardIllustration::from_str(key).is_ok(),
+                "illustration `{}`",
+                key
+            );
+        }
+
+        for key in localizer.effects.keys() {
+            let supported = key == "don"
+                || key == "once_per_turn"
+                || CardKeyword::from_str(key).is_ok()
+                || CardTiming::from_str(key).is_ok();
+            assert!(supported, "effect `{}`", key);
+        }
+    }
+
+    #[test]
+    fn builtin_english_selectors_are_defaults() {
+        let (_, builtin_data) = BUILTIN_LOCALES
+            .iter()
+            .find(|(name, _)| *name == "en")
+            .unwrap();
+        let table: Table = toml::from_str(builtin_data).unwrap();
+        let section = table["selectors"].as_table().unwrap();
+
+        let defaults = Selectors::default();
+        assert_eq!(section.len(), defaults.entries().len());
+        for (key, sel) in defaults.entries() {
+            assert_eq!(
+                section.get(key).and_then(Value::as_str),
+                Some(sel),
+                "selector `{}`",
+                key
+            );
+        }
+    }
+
+    #[test]
+    fn load_without_config_dir_uses_builtin() {
+        let localizer = Localizer::load(Path::new("missing-dir"), LanguageCode::English).unwrap();
+
+        assert_eq!(localizer.hostname, "https://en.onepiece-cardgame.com");
+        assert_eq!(localizer.match_color("Red"), Some(String::from("red")));
+    }
+
+    #[test]
+    fn load_merges_overrides_per_key() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+        fs::write(
+            dir.join("en.toml"),
+            "[colors]\nred = \"Crimson\"\n\n[request]\nmin_delay_ms = 0\n",
+        )
+        .unwrap();
+
+        let localizer = Localizer::load(dir, LanguageCode::English).unwrap();
+
+        assert_eq!(localizer.match_color("Crimson"), Some(String::from("red")));
+        assert_eq!(localizer.match_color("Green"), Some(String::from("green")));
+        assert_eq!(localizer.request.min_delay_ms, 0);
+        assert_eq!(localizer.request.max_attempts, 4);
+        assert_eq!(localizer.hostname, "https://en.onepiece-cardgame.com");
+    }
 }
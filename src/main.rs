@@ -1,21 +1,41 @@
 use std::{env, fs, path::Path, process::ExitCode, time::Instant};
 
-use anyhow::{bail, Result};
+use anyhow::{bail, ensure, Context, Result};
 use clap::Parser;
 use cli::{Cli, LanguageCode};
+use fetcher::Fetcher;
 use localizer::Localizer;
 use log::{error, info};
+use output::OutputFormat;
+use pack::Pack;
+use policy::RequestOverrides;
+use pull::PullOptions;
 
-use scraper::OpTcgScraper;
+use scraper::{CardFailure, OpTcgScraper};
 use storage::DataStore;
 
 mod card;
 mod cli;
+mod diff;
+mod doctor;
+mod download;
+mod error;
+mod export;
+mod fetcher;
 mod interactive;
 mod localizer;
+mod manifest;
+mod merge;
+mod output;
 mod pack;
+mod policy;
+mod product;
+mod pull;
+mod query;
 mod scraper;
+mod selectors;
 mod storage;
+mod validate;
 
 fn main() -> ExitCode {
     let args = Cli::parse();
@@ -26,46 +46,198 @@ fn main() -> ExitCode {
     match process_args(args) {
         Ok(_) => ExitCode::SUCCESS,
         Err(e) => {
-            error!("{}", e);
-            ExitCode::FAILURE
+            error!("{:#}", e);
+            ExitCode::from(error::exit_code(&e))
         }
     }
 }
 
 fn process_args(args: Cli) -> Result<()> {
-    let default_config_dir = env::current_dir()?.join("config");
-    let config_dir = args.config_directory_path.unwrap_or(default_config_dir);
-    info!("using configuration from: {}", config_dir.display());
+    let config_dir = match args.config_directory_path {
+        Some(config_dir) => {
+            ensure!(
+                config_dir.exists(),
+                format!("config directory not found: {}", config_dir.display())
+            );
+            config_dir
+        }
+        None => match Localizer::default_config_dir() {
+            Some(config_dir) => config_dir,
+            None => env::current_dir()?.join("config"),
+        },
+    };
+    info!(
+        "using configuration overrides from: {}",
+        config_dir.display()
+    );
+
+    if let Some(ref fixtures_dir) = args.fixtures_dir {
+        info!("serving requests from fixtures: {}", fixtures_dir.display());
+    }
+    let fetcher = fetcher::create_fetcher(args.fixtures_dir.as_deref())?;
+    let fetcher = fetcher.as_ref();
 
+    let request = RequestOverrides {
+        min_delay_ms: args.min_delay_ms,
+        max_attempts: args.max_attempts,
+        initial_backoff_ms: args.retry_backoff_ms,
+        max_backoff_ms: args.max_backoff_ms,
+    };
+
+    let format = args.format.unwrap_or_default();
     match args.command {
-        cli::Commands::Packs => list_packs(&config_dir, args.language),
-        cli::Commands::Cards { pack_id } => {
-            list_cards(&config_dir, args.language, &pack_id.to_string_lossy())
+        cli::Commands::Packs { by_release } => list_packs(
+            &config_dir,
+            fetcher,
+            request,
+            args.language,
+            format,
+            by_release,
+        ),
+        cli::Commands::Products => {
+            list_products(&config_dir, fetcher, request, args.language, format)
+        }
+        cli::Commands::Cards { pack_id } => list_cards(
+            &config_dir,
+            fetcher,
+            request,
+            args.language,
+            &pack_id.to_string_lossy(),
+            ListCardsOptions {
+                format,
+                group_variants: args.group_variants,
+                lenient: args.lenient,
+            },
+        ),
+        cli::Commands::PullAll {
+            output_dir,
+            languages,
+            with_images,
+            sync,
+            jobs,
+        } => {
+            let languages = if languages.is_empty() {
+                vec![args.language]
+            } else {
+                languages
+            };
+
+            let options = PullOptions {
+                download_images: with_images,
+                sync,
+                jobs,
+                request,
+                lenient: args.lenient,
+            };
+
+            pull_all(&config_dir, fetcher, &languages, &output_dir, options)
+        }
+        cli::Commands::Interactive => {
+            interactive::show_interactive(&config_dir, fetcher, request, args.lenient)
         }
-        cli::Commands::Interactive => interactive::show_interactive(&config_dir),
         cli::Commands::Images {
             pack_id,
             output_dir,
-        } => download_images(
+            sync,
+            jobs,
+        } => {
+            let options = PullOptions {
+                download_images: true,
+                sync,
+                jobs,
+                request,
+                lenient: args.lenient,
+            };
+
+            download_images(
+                &config_dir,
+                fetcher,
+                args.language,
+                &pack_id.to_string_lossy(),
+                &output_dir,
+                options,
+            )
+        }
+        cli::Commands::Query { data_dir, filter } => query::query_cards(
+            &data_dir,
+            args.language,
+            &filter,
+            format,
+            args.group_variants,
+        ),
+        cli::Commands::Merge {
+            data_dir,
+            languages,
+            output,
+        } => merge::merge_languages(&data_dir, &languages, output.as_deref(), format),
+        cli::Commands::Diff { old, new } => diff::diff_data(&old, &new, args.language, args.format),
+        cli::Commands::Export { format } => match format {
+            cli::ExportFormat::Sqlite {
+                data_dir,
+                output,
+                languages,
+            } => {
+                let languages = if languages.is_empty() {
+                    vec![args.language]
+                } else {
+                    languages
+                };
+
+                export::export_sqlite(&data_dir, &languages, &output)
+            }
+        },
+        cli::Commands::Doctor { snapshot } => doctor::run_doctor(
             &config_dir,
+            fetcher,
+            request,
             args.language,
-            &pack_id.to_string_lossy(),
-            &output_dir,
+            snapshot.as_deref(),
         ),
-        cli::Commands::TestConfig => Localizer::find_locales(&config_dir),
+        cli::Commands::TestConfig => validate::validate_locales(&config_dir),
     }
 }
 
+fn pull_all(
+    config_dir: &Path,
+    fetcher: &dyn Fetcher,
+    languages: &[LanguageCode],
+    output_dir: &Path,
+    options: PullOptions,
+) -> Result<()> {
+    info!(
+        "pulling data for {} language(s) into `{}`...",
+        languages.len(),
+        output_dir.display()
+    );
+    let start = Instant::now();
+
+    for language in languages {
+        pull::pull_all(config_dir, fetcher, *language, output_dir, options)?;
+        info!("successfully pulled data for {:?}", language);
+    }
+
+    let duration = start.elapsed();
+    info!("pull_all took: {:?}", duration);
+
+    println!("Final data is available in: {}", output_dir.display());
+    Ok(())
+}
+
 fn download_images(
     config_dir: &Path,
+    fetcher: &dyn Fetcher,
     language: LanguageCode,
     pack_id: &str,
     output_dir: &Path,
+    options: PullOptions,
 ) -> Result<()> {
     let localizer = Localizer::load(config_dir, language)?;
-    let scraper = OpTcgScraper::new(&localizer);
+    let policy = localizer.request.clone().with_overrides(options.request);
+    let scraper = OpTcgScraper::new(&localizer, fetcher)
+        .with_policy(policy)
+        .with_lenient(options.lenient);
 
-    if output_dir.exists() {
+    if output_dir.exists() && !options.sync {
         error!("output directory already `{}` exists", output_dir.display());
         bail!(
             "cannot create directory `{}` to store images because it already exists",
@@ -73,9 +245,11 @@ fn download_images(
         );
     }
 
-    match fs::create_dir_all(output_dir) {
-        Ok(_) => info!("successfully created `{}`", output_dir.display()),
-        Err(e) => bail!("failed to create `{}`: {}", output_dir.display(), e),
+    if !output_dir.exists() {
+        match fs::create_dir_all(output_dir) {
+            Ok(_) => info!("successfully created `{}`", output_dir.display()),
+            Err(e) => bail!("failed to create `{}`: {}", output_dir.display(), e),
+        }
     }
 
     info!("fetching all cards for pack `{}`...", pack_id);
@@ -99,39 +273,66 @@ fn download_images(
     info!("downloading images for pack `{}`...", pack_id);
     let start = Instant::now();
 
-    for (idx, card) in cards.iter().enumerate() {
+    let mut pending = Vec::new();
+    for card in cards.iter() {
+        let img_path = output_dir.join(DataStore::get_img_filename(card)?);
+        if options.sync && img_path.exists() {
+            info!("image `{}` already exists, skipping", img_path.display());
+            continue;
+        }
+
+        pending.push(card.clone());
+    }
+
+    download::download_card_images(&scraper, &pending, options.jobs, |done, card, img_data| {
         let img_filename = DataStore::get_img_filename(card)?;
         let img_path = output_dir.join(img_filename);
 
-        let img_data = scraper.download_card_image(card)?;
         DataStore::write_image_to_file(img_data, &img_path)?;
 
         info!(
             "[{}/{}] saved image `{}` to `{}`",
-            idx + 1,
-            cards.len(),
+            done,
+            pending.len(),
             card.img_url,
             img_path.display()
         );
-    }
+        Ok(())
+    })?;
 
     let duration = start.elapsed();
     info!("downloading images took: {:?}", duration);
+
+    CardFailure::print_summary(&scraper.take_failures());
     Ok(())
 }
 
-fn list_packs(config_dir: &Path, language: LanguageCode) -> Result<()> {
+fn list_packs(
+    config_dir: &Path,
+    fetcher: &dyn Fetcher,
+    request: RequestOverrides,
+    language: LanguageCode,
+    format: OutputFormat,
+    by_release: bool,
+) -> Result<()> {
     let localizer = Localizer::load(config_dir, language)?;
-    let scraper = OpTcgScraper::new(&localizer);
+    let policy = localizer.request.clone().with_overrides(request);
+    let scraper = OpTcgScraper::new(&localizer, fetcher).with_policy(policy);
 
     info!("fetching all pack ids...");
     let start = Instant::now();
 
-    let packs = scraper.fetch_all_packs()?;
+    let mut packs = scraper.fetch_all_packs()?;
     info!("successfully fetched {} packs!", packs.len());
 
-    let json = serde_json::to_string(&packs)?;
-    println!("{}", json);
+    if by_release {
+        let products = scraper
+            .fetch_all_products(&packs)
+            .context("failed to fetch the release dates of the packs")?;
+        Pack::sort_by_release(&mut packs, &products);
+    }
+
+    output::print_packs(&packs, format)?;
 
     let duration = start.elapsed();
 
@@ -139,9 +340,55 @@ fn list_packs(config_dir: &Path, language: LanguageCode) -> Result<()> {
     Ok(())
 }
 
-fn list_cards(config_dir: &Path, language: LanguageCode, pack_id: &str) -> Result<()> {
+fn list_products(
+    config_dir: &Path,
+    fetcher: &dyn Fetcher,
+    request: RequestOverrides,
+    language: LanguageCode,
+    format: OutputFormat,
+) -> Result<()> {
+    let localizer = Localizer::load(config_dir, language)?;
+    let policy = localizer.request.clone().with_overrides(request);
+    let scraper = OpTcgScraper::new(&localizer, fetcher).with_policy(policy);
+
+    info!("fetching all products...");
+    let start = Instant::now();
+
+    let packs = scraper.fetch_all_packs()?;
+    let products = scraper.fetch_all_products(&packs)?;
+    info!(
+        "successfully matched {} products to {} packs!",
+        products.len(),
+        packs.len()
+    );
+
+    output::print_products(&products, format)?;
+
+    let duration = start.elapsed();
+
+    info!("list_products took: {:?}", duration);
+    Ok(())
+}
+
+struct ListCardsOptions {
+    format: OutputFormat,
+    group_variants: bool,
+    lenient: bool,
+}
+
+fn list_cards(
+    config_dir: &Path,
+    fetcher: &dyn Fetcher,
+    request: RequestOverrides,
+    language: LanguageCode,
+    pack_id: &str,
+    options: ListCardsOptions,
+) -> Result<()> {
     let localizer = Localizer::load(config_dir, language)?;
-    let scraper = OpTcgScraper::new(&localizer);
+    let policy = localizer.request.clone().with_overrides(request);
+    let scraper = OpTcgScraper::new(&localizer, fetcher)
+        .with_policy(policy)
+        .with_lenient(options.lenient);
 
     info!("fetching all cards...");
     let start = Instant::now();
@@ -158,8 +405,8 @@ fn list_cards(config_dir: &Path, language: LanguageCode, pack_id: &str) -> Resul
         pack_id
     );
 
-    let json = serde_json::to_string(&cards)?;
-    println!("{}", json);
+    output::print_cards(&cards, options.format, options.group_variants)?;
+    CardFailure::print_summary(&scraper.take_failures());
 
     let duration = start.elapsed();
 
@@ -0,0 +1,201 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+use crate::cli::LanguageCode;
+
+/// Bookkeeping of what has already been pulled into a `DataStore`, used to sync incrementally.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Manifest {
+    pub locales: BTreeMap<String, LocaleManifest>,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct LocaleManifest {
+    /// Set when a sync starts and cleared once it completes, a value means the last run crashed
+    pub sync_started_at: Option<DateTime<Utc>>,
+    pub synced_at: Option<DateTime<Utc>>,
+    pub packs_hash: Option<String>,
+    pub packs: BTreeMap<String, PackEntry>,
+    /// Box art of the packs, by pack id
+    #[serde(default)]
+    pub pack_images: BTreeMap<String, ImageEntry>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PackEntry {
+    pub card_count: usize,
+    pub content_hash: String,
+    pub fetched_at: DateTime<Utc>,
+    pub images: BTreeMap<String, ImageEntry>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ImageEntry {
+    pub img_url: String,
+    pub content_hash: String,
+    pub fetched_at: DateTime<Utc>,
+}
+
+impl Manifest {
+    pub fn locale_mut(&mut self, language: LanguageCode) -> &mut LocaleManifest {
+        let key = language.to_path().to_string_lossy().to_string();
+        self.locales.entry(key).or_default()
+    }
+
+    pub fn hash(data: &[u8]) -> String {
+        format!("{:x}", Sha256::digest(data))
+    }
+}
+
+impl LocaleManifest {
+    /// Start of the interrupted run to resume, if any, or a fresh start time otherwise.
+    pub fn begin_sync(&mut self) -> (DateTime<Utc>, bool) {
+        match self.sync_started_at {
+            Some(started_at) => (started_at, true),
+            None => {
+                let started_at = Utc::now();
+                self.sync_started_at = Some(started_at);
+                (started_at, false)
+            }
+        }
+    }
+
+    pub fn end_sync(&mut self) {
+        self.sync_started_at = None;
+        self.synced_at = Some(Utc::now());
+    }
+
+    /// Whether the pack was completely pulled during the run that started at `started_at`.
+    pub fn is_pack_synced_since(&self, pack_id: &str, started_at: DateTime<Utc>) -> bool {
+        self.packs
+            .get(pack_id)
+            .is_some_and(|entry| entry.fetched_at >= started_at)
+    }
+
+    pub fn is_pack_unchanged(&self, pack_id: &str, content_hash: &str) -> bool {
+        self.packs
+            .get(pack_id)
+            .is_some_and(|entry| entry.content_hash == content_hash)
+    }
+
+    pub fn is_pack_image_unchanged(&self, pack_id: &str, img_url: &str) -> bool {
+        self.pack_images
+            .get(pack_id)
+            .is_some_and(|image| image.img_url == img_url)
+    }
+}
+
+impl PackEntry {
+    /// Images carry a cache-busting query in their url, so an unchanged url means unchanged content.
+    pub fn is_image_unchanged(&self, card_id: &str, img_url: &str) -> bool {
+        self.images
+            .get(card_id)
+            .is_some_and(|image| image.img_url == img_url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn get_test_entry(fetched_at: DateTime<Utc>) -> PackEntry {
+        let mut images = BTreeMap::new();
+        images.insert(
+            String::from("OP01-001"),
+            ImageEntry {
+                img_url: String::from("../images/cardlist/card/OP01-001.png?240322"),
+                content_hash: String::from("abc"),
+                fetched_at,
+            },
+        );
+
+        PackEntry {
+            card_count: 1,
+            content_hash: String::from("123"),
+            fetched_at,
+            images,
+        }
+    }
+
+    #[test]
+    fn hash_is_sha256_hex() {
+        assert_eq!(
+            Manifest::hash(b"vegapull"),
+            format!("{:x}", Sha256::digest(b"vegapull"))
+        );
+        assert_eq!(Manifest::hash(b"").len(), 64);
+    }
+
+    #[test]
+    fn begin_sync_fresh_returns_not_resuming() {
+        let mut locale = LocaleManifest::default();
+
+        let (_, resuming) = locale.begin_sync();
+        assert!(!resuming);
+        assert!(locale.sync_started_at.is_some());
+    }
+
+    #[test]
+    fn begin_sync_after_crash_returns_resuming() {
+        let mut locale = LocaleManifest::default();
+        let (started_at, _) = locale.begin_sync();
+
+        let (resumed_at, resuming) = locale.begin_sync();
+        assert!(resuming);
+        assert_eq!(resumed_at, started_at);
+    }
+
+    #[test]
+    fn end_sync_clears_started_at() {
+        let mut locale = LocaleManifest::default();
+        locale.begin_sync();
+        locale.end_sync();
+
+        assert!(locale.sync_started_at.is_none());
+        assert!(locale.synced_at.is_some());
+    }
+
+    #[test]
+    fn is_pack_synced_since_compares_fetch_time() {
+        let now = Utc::now();
+        let mut locale = LocaleManifest::default();
+        locale
+            .packs
+            .insert(String::from("569101"), get_test_entry(now));
+
+        assert!(locale.is_pack_synced_since("569101", now - Duration::minutes(1)));
+        assert!(!locale.is_pack_synced_since("569101", now + Duration::minutes(1)));
+        assert!(!locale.is_pack_synced_since("569001", now));
+    }
+
+    #[test]
+    fn is_image_unchanged_compares_img_url() {
+        let entry = get_test_entry(Utc::now());
+
+        let img_url = "../images/cardlist/card/OP01-001.png?240322";
+        assert!(entry.is_image_unchanged("OP01-001", img_url));
+        assert!(!entry.is_image_unchanged("OP01-001", "../images/new.png"));
+        assert!(!entry.is_image_unchanged("OP01-016", img_url));
+    }
+
+    #[test]
+    fn is_pack_image_unchanged_compares_img_url() {
+        let mut locale = LocaleManifest::default();
+        let img_url = "../images/products/boosters/op01/img_thumbnail.png";
+        locale.pack_images.insert(
+            String::from("569101"),
+            ImageEntry {
+                img_url: String::from(img_url),
+                content_hash: String::from("abc"),
+                fetched_at: Utc::now(),
+            },
+        );
+
+        assert!(locale.is_pack_image_unchanged("569101", img_url));
+        assert!(!locale.is_pack_image_unchanged("569101", "../images/new.png"));
+        assert!(!locale.is_pack_image_unchanged("569001", img_url));
+    }
+}
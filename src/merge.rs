@@ -0,0 +1,341 @@
+use anyhow::{bail, ensure, Context, Result};
+use log::{debug, info, warn};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::{self, BufWriter},
+    path::Path,
+    time::Instant,
+};
+
+use crate::{
+    card::{Card, CardAbilities, CardAttribute, CardCategory, CardColor, CardRarity, CardVariant},
+    cli::LanguageCode,
+    output::{self, OutputFormat},
+    storage::DataStore,
+};
+
+/// Text of a card as printed in one language.
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct CardText {
+    pub pack_id: String,
+    pub name: String,
+    pub types: Vec<String>,
+    pub effect: String,
+    pub trigger: Option<String>,
+    pub img_url: String,
+}
+
+impl From<&Card> for CardText {
+    fn from(card: &Card) -> Self {
+        CardText {
+            pack_id: card.pack_id.clone(),
+            name: card.name.clone(),
+            types: card.types.clone(),
+            effect: card.effect.clone(),
+            trigger: card.trigger.clone(),
+            img_url: card.img_url.clone(),
+        }
+    }
+}
+
+/// Card joined by id across languages, gameplay stats are shared and text is kept per language.
+#[derive(Clone, Debug, Serialize)]
+pub struct MultilingualCard {
+    pub id: String,
+    pub base_id: String,
+    pub variant: Option<CardVariant>,
+    pub rarity: CardRarity,
+    pub category: CardCategory,
+    pub colors: Vec<CardColor>,
+    pub cost: Option<i32>,
+    pub attributes: Vec<CardAttribute>,
+    pub power: Option<i32>,
+    pub counter: Option<i32>,
+    #[serde(flatten)]
+    pub abilities: CardAbilities,
+    /// Keyed by language directory name, e.g. `english`
+    pub languages: BTreeMap<String, CardText>,
+}
+
+impl MultilingualCard {
+    fn new(card: &Card) -> Self {
+        MultilingualCard {
+            id: card.id.clone(),
+            base_id: card.base_id.clone(),
+            variant: card.variant.clone(),
+            rarity: card.rarity.clone(),
+            category: card.category.clone(),
+            colors: card.colors.clone(),
+            cost: card.cost,
+            attributes: card.attributes.clone(),
+            power: card.power,
+            counter: card.counter,
+            abilities: card.abilities.clone(),
+            languages: BTreeMap::new(),
+        }
+    }
+
+    /// Stats that should not depend on the region the card was printed for.
+    fn has_same_stats(&self, card: &Card) -> bool {
+        self.category == card.category
+            && self.colors == card.colors
+            && self.cost == card.cost
+            && self.attributes == card.attributes
+            && self.power == card.power
+            && self.counter == card.counter
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct MergeResult {
+    pub cards: Vec<MultilingualCard>,
+    /// Ids of the merged cards each language does not have, keyed by language directory name
+    pub missing: BTreeMap<String, Vec<String>>,
+}
+
+fn language_key(language: LanguageCode) -> String {
+    language.to_path().to_string_lossy().to_string()
+}
+
+/// Joins cards by id, the first language a card appears in provides its shared stats.
+///
+/// Cards listed in several packs of the same language (reprints) are kept under the first one.
+pub fn merge_cards(cards_by_language: &[(LanguageCode, Vec<Card>)]) -> MergeResult {
+    let mut merged: Vec<MultilingualCard> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+
+    for (language, cards) in cards_by_language {
+        let key = language_key(*language);
+        for card in cards {
+            let position = match positions.get(&card.id) {
+                Some(position) => *position,
+                None => {
+                    positions.insert(card.id.clone(), merged.len());
+                    merged.push(MultilingualCard::new(card));
+                    merged.len() - 1
+                }
+            };
+
+            let merged_card = &mut merged[position];
+            if merged_card.languages.contains_key(&key) {
+                debug!("card `{}` already merged for {}, skipping", card.id, key);
+                continue;
+            }
+
+            if !merged_card.has_same_stats(card) {
+                warn!(
+                    "card `{}` has different stats in {}, keeping the first ones",
+                    card.id, key
+                );
+            }
+
+            merged_card
+                .languages
+                .insert(key.clone(), CardText::from(card));
+        }
+    }
+
+    let mut missing = BTreeMap::new();
+    for (language, _) in cards_by_language {
+        let key = language_key(*language);
+        let ids: Vec<String> = merged
+            .iter()
+            .filter(|card| !card.languages.contains_key(&key))
+            .map(|card| card.id.clone())
+            .collect();
+        missing.insert(key, ids);
+    }
+
+    MergeResult {
+        cards: merged,
+        missing,
+    }
+}
+
+fn load_all_cards(store: &DataStore) -> Result<Vec<Card>> {
+    let mut cards = Vec::new();
+    for pack in store.load_packs()? {
+        cards.extend(store.load_cards(&pack.id)?);
+    }
+
+    Ok(cards)
+}
+
+/// Merges the cards pulled into `data_dir` for `languages` and reports the cards missing from
+/// each language on stderr.
+pub fn merge_languages(
+    data_dir: &Path,
+    languages: &[LanguageCode],
+    output_path: Option<&Path>,
+    format: OutputFormat,
+) -> Result<()> {
+    ensure!(languages.len() >= 2, "at least two languages are required");
+    if let OutputFormat::Csv | OutputFormat::Tsv = format {
+        bail!("merged cards can only be written as JSON");
+    }
+
+    info!("merging cards of {} languages...", languages.len());
+    let start = Instant::now();
+
+    let mut cards_by_language = Vec::new();
+    for language in languages {
+        let store = DataStore::new(data_dir, *language);
+        let cards = load_all_cards(&store)?;
+        debug!("loaded {} cards for {:?}", cards.len(), language);
+        cards_by_language.push((*language, cards));
+    }
+
+    let result = merge_cards(&cards_by_language);
+
+    match output_path {
+        Some(path) => {
+            let file = File::create(path)
+                .with_context(|| format!("Failed to create file: {}", path.display()))?;
+            output::write_json(BufWriter::new(file), &result.cards, format)?;
+        }
+        None => output::write_json(io::stdout().lock(), &result.cards, format)?,
+    }
+
+    eprintln!("Merged {} cards", result.cards.len());
+    for (language, ids) in result.missing.iter() {
+        if ids.is_empty() {
+            eprintln!("{}: no missing cards", language);
+        } else {
+            eprintln!(
+                "{}: {} missing cards: {}",
+                language,
+                ids.len(),
+                ids.join(", ")
+            );
+        }
+    }
+
+    let duration = start.elapsed();
+    info!("merge_languages took: {:?}", duration);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{card::test_util, pack::Pack};
+    use std::fs;
+
+    fn get_test_card(id: &str, name: &str) -> Card {
+        Card {
+            name: name.to_string(),
+            ..test_util::card(id)
+        }
+    }
+
+    fn get_test_pack() -> Pack {
+        serde_json::from_str(
+            r#"{"id":"569101","raw_title":"BOOSTER PACK -ROMANCE DAWN- [OP01]",
+                "title_parts":{"prefix":"BOOSTER PACK","title":"ROMANCE DAWN","label":"OP01"}}"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn merge_cards_joins_text_by_id() {
+        let mut japanese = get_test_card("OP01-016", "ナミ");
+        japanese.pack_id = String::from("550101");
+        japanese.types = vec![String::from("麦わらの一味")];
+
+        let result = merge_cards(&[
+            (
+                LanguageCode::English,
+                vec![get_test_card("OP01-016", "Nami")],
+            ),
+            (LanguageCode::Japanese, vec![japanese]),
+        ]);
+
+        assert_eq!(result.cards.len(), 1);
+        let card = &result.cards[0];
+        assert_eq!(card.power, Some(2000));
+        assert_eq!(card.languages["english"].name, "Nami");
+        assert_eq!(card.languages["japanese"].name, "ナミ");
+        assert_eq!(card.languages["japanese"].pack_id, "550101");
+        assert!(result.missing.values().all(|ids| ids.is_empty()));
+    }
+
+    #[test]
+    fn merge_cards_reports_missing_cards() {
+        let result = merge_cards(&[
+            (
+                LanguageCode::English,
+                vec![get_test_card("OP01-016", "Nami")],
+            ),
+            (
+                LanguageCode::Japanese,
+                vec![
+                    get_test_card("OP01-016", "ナミ"),
+                    get_test_card("OP09-001", "シャンクス"),
+                ],
+            ),
+        ]);
+
+        let ids: Vec<&str> = result.cards.iter().map(|card| card.id.as_str()).collect();
+        assert_eq!(ids, vec!["OP01-016", "OP09-001"]);
+        assert_eq!(result.missing["english"], vec![String::from("OP09-001")]);
+        assert!(result.missing["japanese"].is_empty());
+    }
+
+    #[test]
+    fn merge_cards_keeps_first_reprint() {
+        let mut reprint = get_test_card("OP01-016", "Nami");
+        reprint.pack_id = String::from("569201");
+
+        let result = merge_cards(&[(
+            LanguageCode::English,
+            vec![get_test_card("OP01-016", "Nami"), reprint],
+        )]);
+
+        assert_eq!(result.cards.len(), 1);
+        assert_eq!(result.cards[0].languages["english"].pack_id, "569101");
+    }
+
+    #[test]
+    fn merge_languages_single_language_returns_err() {
+        let result = merge_languages(
+            Path::new("missing-dir"),
+            &[LanguageCode::English],
+            None,
+            OutputFormat::Json,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn merge_languages_writes_output_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+
+        for (language, name) in [
+            (LanguageCode::English, "Nami"),
+            (LanguageCode::Japanese, "ナミ"),
+        ] {
+            let store = DataStore::new(dir, language);
+            store.write_packs(&vec![get_test_pack()]).unwrap();
+            store
+                .write_cards("569101", &vec![get_test_card("OP01-016", name)])
+                .unwrap();
+        }
+
+        let output_path = dir.join("merged.json");
+        merge_languages(
+            dir,
+            &[LanguageCode::English, LanguageCode::Japanese],
+            Some(&output_path),
+            OutputFormat::Json,
+        )
+        .unwrap();
+
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&output_path).unwrap()).unwrap();
+        assert_eq!(json[0]["id"], "OP01-016");
+        assert_eq!(json[0]["languages"]["japanese"]["name"], "ナミ");
+    }
+}
@@ -0,0 +1,417 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::io::{self, Write};
+
+use crate::{
+    card::{variant::CardVariantKind, Card, CardCategory, CardIllustration, CardRarity},
+    pack::{Pack, ProductType},
+    product::ProductInfo,
+};
+
+#[derive(ValueEnum, Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Compact JSON array
+    #[default]
+    Json,
+    /// Indented JSON array
+    JsonPretty,
+    /// One JSON object per line
+    Ndjson,
+    /// Comma-separated values with a header row
+    Csv,
+    /// Tab-separated values with a header row
+    Tsv,
+}
+
+/// Separator used to flatten list fields into a single spreadsheet cell.
+const LIST_SEPARATOR: &str = "/";
+
+#[derive(Debug, Serialize)]
+struct PackRow<'a> {
+    id: &'a str,
+    raw_title: &'a str,
+    prefix: Option<&'a str>,
+    title: &'a str,
+    label: Option<&'a str>,
+    product_type: ProductType,
+    set_code: Option<&'a str>,
+    set_number: Option<u32>,
+}
+
+impl<'a> From<&'a Pack> for PackRow<'a> {
+    fn from(pack: &'a Pack) -> Self {
+        PackRow {
+            id: &pack.id,
+            raw_title: &pack.raw_title,
+            prefix: pack.title_parts.prefix.as_deref(),
+            title: &pack.title_parts.title,
+            label: pack.title_parts.label.as_deref(),
+            product_type: pack.product_type,
+            set_code: pack.set_code.as_deref(),
+            set_number: pack.set_number,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct CardRow<'a> {
+    id: &'a str,
+    base_id: &'a str,
+    variant_kind: Option<&'a CardVariantKind>,
+    variant_index: Option<u32>,
+    pack_id: &'a str,
+    name: &'a str,
+    rarity: &'a CardRarity,
+    category: &'a CardCategory,
+    block: Option<i32>,
+    product: Option<&'a str>,
+    colors: String,
+    cost: Option<i32>,
+    attributes: String,
+    power: Option<i32>,
+    counter: Option<i32>,
+    types: String,
+    effect: &'a str,
+    trigger: Option<&'a str>,
+    keywords: String,
+    timings: String,
+    don_requirements: String,
+    once_per_turn: bool,
+    img_url: &'a str,
+    img_full_url: Option<&'a str>,
+    illustration: Option<&'a CardIllustration>,
+    illustrator_name: Option<&'a str>,
+}
+
+impl<'a> TryFrom<&'a Card> for CardRow<'a> {
+    type Error = anyhow::Error;
+
+    fn try_from(card: &'a Card) -> Result<Self> {
+        Ok(CardRow {
+            id: &card.id,
+            base_id: &card.base_id,
+            variant_kind: card.variant.as_ref().map(|v| &v.kind),
+            variant_index: card.variant.as_ref().map(|v| v.index),
+            pack_id: &card.pack_id,
+            name: &card.name,
+            rarity: &card.rarity,
+            category: &card.category,
+            block: card.block,
+            product: card.product.as_deref(),
+            colors: join_names(&card.colors)?,
+            cost: card.cost,
+            attributes: join_names(&card.attributes)?,
+            power: card.power,
+            counter: card.counter,
+            types: card.types.join(LIST_SEPARATOR),
+            effect: &card.effect,
+            trigger: card.trigger.as_deref(),
+            keywords: join_names(&card.abilities.keywords)?,
+            timings: join_names(&card.abilities.timings)?,
+            don_requirements: join_names(&card.abilities.don_requirements)?,
+            once_per_turn: card.abilities.once_per_turn,
+            img_url: &card.img_url,
+            img_full_url: card.img_full_url.as_deref(),
+            illustration: card.illustration.as_ref(),
+            illustrator_name: card.illustrator_name.as_deref(),
+        })
+    }
+}
+
+/// Every printing of the same card, the base print first when it is part of the list.
+#[derive(Debug, Serialize)]
+pub struct CardGroup<'a> {
+    pub base_id: &'a str,
+    pub prints: Vec<&'a Card>,
+}
+
+/// Groups cards by base id, keeping the order in which base cards first appear.
+pub fn group_variants(cards: &[Card]) -> Vec<CardGroup<'_>> {
+    let mut groups: Vec<CardGroup> = Vec::new();
+    for card in cards {
+        let base_id = card.base_id.as_str();
+
+        match groups.iter_mut().find(|group| group.base_id == base_id) {
+            Some(group) => group.prints.push(card),
+            None => groups.push(CardGroup {
+                base_id,
+                prints: vec![card],
+            }),
+        }
+    }
+
+    for group in groups.iter_mut() {
+        // Stable sort, variants keep their relative order
+        group.prints.sort_by_key(|card| card.variant.is_some());
+    }
+
+    groups
+}
+
+fn join_names<T: Serialize>(values: &[T]) -> Result<String> {
+    let mut names = Vec::new();
+    for value in values {
+        match serde_json::to_value(value)? {
+            serde_json::Value::String(name) => names.push(name),
+            other => names.push(other.to_string()),
+        }
+    }
+
+    Ok(names.join(LIST_SEPARATOR))
+}
+
+pub fn print_packs(packs: &[Pack], format: OutputFormat) -> Result<()> {
+    write_packs(io::stdout().lock(), packs, format)
+}
+
+pub fn print_products(products: &[ProductInfo], format: OutputFormat) -> Result<()> {
+    write_products(io::stdout().lock(), products, format)
+}
+
+pub fn print_cards(cards: &[Card], format: OutputFormat, group: bool) -> Result<()> {
+    write_cards(io::stdout().lock(), cards, format, group)
+}
+
+pub fn write_packs<W: Write>(writer: W, packs: &[Pack], format: OutputFormat) -> Result<()> {
+    match format {
+        OutputFormat::Csv | OutputFormat::Tsv => {
+            write_table(writer, packs.iter().map(PackRow::from), format)
+        }
+        _ => write_json(writer, packs, format),
+    }
+}
+
+pub fn write_products<W: Write>(
+    writer: W,
+    products: &[ProductInfo],
+    format: OutputFormat,
+) -> Result<()> {
+    match format {
+        OutputFormat::Csv | OutputFormat::Tsv => write_table(writer, products, format),
+        _ => write_json(writer, products, format),
+    }
+}
+
+/// Writes `cards` in the given format, with `group` every printing of a card is listed under
+/// its base id in JSON formats and right after its base print in tables.
+pub fn write_cards<W: Write>(
+    writer: W,
+    cards: &[Card],
+    format: OutputFormat,
+    group: bool,
+) -> Result<()> {
+    match (format, group) {
+        (OutputFormat::Csv | OutputFormat::Tsv, false) => {
+            let rows = cards
+                .iter()
+                .map(CardRow::try_from)
+                .collect::<Result<Vec<_>>>()?;
+            write_table(writer, rows, format)
+        }
+        (OutputFormat::Csv | OutputFormat::Tsv, true) => {
+            let rows = group_variants(cards)
+                .into_iter()
+                .flat_map(|group| group.prints)
+                .map(CardRow::try_from)
+                .collect::<Result<Vec<_>>>()?;
+            write_table(writer, rows, format)
+        }
+        (_, false) => write_json(writer, cards, format),
+        (_, true) => write_json(writer, &group_variants(cards), format),
+    }
+}
+
+pub fn write_json<W: Write, T: Serialize>(
+    mut writer: W,
+    items: &[T],
+    format: OutputFormat,
+) -> Result<()> {
+    match format {
+        OutputFormat::JsonPretty => serde_json::to_writer_pretty(&mut writer, items)?,
+        OutputFormat::Ndjson => {
+            for item in items {
+                serde_json::to_writer(&mut writer, item)?;
+                writeln!(writer)?;
+            }
+
+            return Ok(());
+        }
+        _ => serde_json::to_writer(&mut writer, items)?,
+    }
+
+    writeln!(writer)?;
+    Ok(())
+}
+
+fn write_table<W, I, R>(writer: W, rows: I, format: OutputFormat) -> Result<()>
+where
+    W: Write,
+    I: IntoIterator<Item = R>,
+    R: Serialize,
+{
+    let delimiter = match format {
+        OutputFormat::Tsv => b'\t',
+        _ => b',',
+    };
+
+    let mut table = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(writer);
+
+    for row in rows {
+        table.serialize(row)?;
+    }
+
+    table.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card::{
+        effect::CardTiming, test_util, CardAbilities, CardAttribute, CardColor, CardVariant,
+    };
+
+    fn get_test_card() -> Card {
+        Card {
+            name: String::from("Roronoa Zoro"),
+            rarity: CardRarity::Leader,
+            category: CardCategory::Leader,
+            colors: vec![CardColor::Red, CardColor::Green],
+            cost: Some(5),
+            attributes: vec![CardAttribute::Slash],
+            power: Some(5000),
+            counter: None,
+            types: vec![String::from("Supernovas"), String::from("Straw Hat Crew")],
+            effect: String::from("[DON!! x1] [Your Turn] All of your Characters gain +1000 power."),
+            abilities: CardAbilities {
+                timings: vec![CardTiming::YourTurn],
+                don_requirements: vec![1],
+                ..CardAbilities::default()
+            },
+            ..test_util::card("OP01-001")
+        }
+    }
+
+    fn get_test_pack() -> Pack {
+        serde_json::from_str(
+            r#"{"id":"569101","raw_title":"BOOSTER PACK -ROMANCE DAWN- [OP01]",
+                "title_parts":{"prefix":"BOOSTER PACK","title":"ROMANCE DAWN","label":"OP01"},
+                "product_type":"Booster","set_code":"OP-01","set_number":1}"#,
+        )
+        .unwrap()
+    }
+
+    fn render_cards(format: OutputFormat) -> String {
+        let mut buffer = Vec::new();
+        write_cards(
+            &mut buffer,
+            &[get_test_card(), get_test_card()],
+            format,
+            false,
+        )
+        .unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn write_cards_csv_flattens_lists() {
+        let output = render_cards(OutputFormat::Csv);
+        let mut lines = output.lines();
+
+        assert_eq!(
+            lines.next().unwrap(),
+            "id,base_id,variant_kind,variant_index,pack_id,name,rarity,category,block,product,colors,cost,attributes,power,counter,types,effect,trigger,keywords,timings,don_requirements,once_per_turn,img_url,img_full_url,illustration,illustrator_name"
+        );
+        assert_eq!(
+            lines.next().unwrap(),
+            "OP01-001,OP01-001,,,569101,Roronoa Zoro,Leader,Leader,,,Red/Green,5,Slash,5000,,Supernovas/Straw Hat Crew,[DON!! x1] [Your Turn] All of your Characters gain +1000 power.,,,YourTurn,1,false,../images/cardlist/card/OP01-001.png,,,"
+        );
+    }
+
+    #[test]
+    fn write_cards_tsv_uses_tabs() {
+        let output = render_cards(OutputFormat::Tsv);
+        let header = output.lines().next().unwrap();
+
+        assert!(header.starts_with("id\tbase_id\tvariant_kind\t"));
+    }
+
+    #[test]
+    fn write_cards_ndjson_writes_one_line_per_card() {
+        let output = render_cards(OutputFormat::Ndjson);
+        let lines: Vec<_> = output.lines().collect();
+
+        assert_eq!(lines.len(), 2);
+        let card: Card = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(card.id, "OP01-001");
+    }
+
+    #[test]
+    fn write_cards_json_is_an_array() {
+        let output = render_cards(OutputFormat::Json);
+        let cards: Vec<Card> = serde_json::from_str(&output).unwrap();
+
+        assert_eq!(cards.len(), 2);
+        assert_eq!(output.lines().count(), 1);
+    }
+
+    fn get_test_variant(id: &str, index: u32) -> Card {
+        let mut card = get_test_card();
+        card.id = format!("{}_p{}", card.base_id, index);
+        card.variant = Some(CardVariant {
+            kind: CardVariantKind::Parallel,
+            index,
+        });
+        assert_eq!(card.id, id);
+        card
+    }
+
+    #[test]
+    fn group_variants_puts_base_print_first() {
+        let mut other = get_test_card();
+        other.id = String::from("OP01-016");
+        other.base_id = String::from("OP01-016");
+
+        let cards = vec![
+            get_test_variant("OP01-001_p1", 1),
+            other,
+            get_test_card(),
+            get_test_variant("OP01-001_p2", 2),
+        ];
+
+        let groups = group_variants(&cards);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].base_id, "OP01-001");
+
+        let ids: Vec<_> = groups[0].prints.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["OP01-001", "OP01-001_p1", "OP01-001_p2"]);
+        assert_eq!(groups[1].prints.len(), 1);
+    }
+
+    #[test]
+    fn write_cards_grouped_json_nests_prints() {
+        let cards = vec![get_test_card(), get_test_variant("OP01-001_p1", 1)];
+        let mut buffer = Vec::new();
+        write_cards(&mut buffer, &cards, OutputFormat::Json, true).unwrap();
+
+        let groups: serde_json::Value = serde_json::from_slice(&buffer).unwrap();
+        assert_eq!(groups[0]["base_id"], "OP01-001");
+        assert_eq!(groups[0]["prints"][1]["id"], "OP01-001_p1");
+        assert_eq!(groups[0]["prints"][1]["variant"]["kind"], "Parallel");
+    }
+
+    #[test]
+    fn write_packs_csv_flattens_title_parts() {
+        let mut buffer = Vec::new();
+        write_packs(&mut buffer, &[get_test_pack()], OutputFormat::Csv).unwrap();
+        let output = String::from_utf8(buffer).unwrap();
+
+        assert_eq!(
+            output,
+            "id,raw_title,prefix,title,label,product_type,set_code,set_number\n569101,BOOSTER PACK -ROMANCE DAWN- [OP01],BOOSTER PACK,ROMANCE DAWN,OP01,Booster,OP-01,1\n"
+        );
+    }
+}
@@ -2,34 +2,174 @@ use anyhow::Result;
 use regex::Regex;
 use scraper::ElementRef;
 use serde::{Deserialize, Serialize};
-use std::fmt;
+use std::{cmp::Ordering, fmt, sync::OnceLock};
+
+use crate::{error::VegaError, product::ProductInfo};
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Pack {
     pub id: String,
     pub raw_title: String,
     pub title_parts: TitleParts,
+    // Derived from the title parts, unset in packs pulled by older versions
+    #[serde(default)]
+    pub product_type: ProductType,
+    #[serde(default)]
+    pub set_code: Option<String>, // Normalized label, e.g. `OP-07` for `OP07`
+    /// Number of the set within its product line, e.g. 7 for `OP-07`. Lines are numbered
+    /// independently, so it only orders packs of the same product type, see `sort_by_release`
+    /// to order packs across lines.
+    #[serde(default, alias = "release_order")]
+    pub set_number: Option<u32>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ProductType {
+    Booster,
+    StarterDeck,
+    ExtraBooster,
+    PremiumBooster,
+    Promo,
+    #[default]
+    Other,
+}
+
+impl ProductType {
+    /// Keywords looked for in the prefix, most specific first
+    const PREFIX_KEYWORDS: [(&'static str, ProductType); 5] = [
+        ("PREMIUM BOOSTER", ProductType::PremiumBooster),
+        ("EXTRA BOOSTER", ProductType::ExtraBooster),
+        ("BOOSTER", ProductType::Booster),
+        ("DECK", ProductType::StarterDeck),
+        ("PROMO", ProductType::Promo),
+    ];
+
+    /// Letters of the set codes of each product line
+    const SET_CODE_SERIES: [(&'static str, ProductType); 5] = [
+        ("OP", ProductType::Booster),
+        ("ST", ProductType::StarterDeck),
+        ("EB", ProductType::ExtraBooster),
+        ("PRB", ProductType::PremiumBooster),
+        ("P", ProductType::Promo),
+    ];
+
+    fn from_prefix(prefix: &str) -> Option<ProductType> {
+        let prefix = prefix.to_uppercase();
+        Self::PREFIX_KEYWORDS
+            .iter()
+            .find(|(keyword, _)| prefix.contains(keyword))
+            .map(|(_, product_type)| *product_type)
+    }
+
+    fn from_series(series: &str) -> Option<ProductType> {
+        Self::SET_CODE_SERIES
+            .iter()
+            .find(|(letters, _)| *letters == series)
+            .map(|(_, product_type)| *product_type)
+    }
+
+    /// Product type named by the prefix (only English prefixes are known), falling back to the
+    /// letters of the set code for other languages.
+    pub fn infer(title_parts: &TitleParts) -> ProductType {
+        let from_prefix = title_parts.prefix.as_deref().and_then(Self::from_prefix);
+        let from_label = || {
+            let label = title_parts.label.as_deref()?;
+            let (series, _) = Pack::split_set_code(label)?;
+            Self::from_series(&series)
+        };
+        let from_title = || Self::from_prefix(&title_parts.title).filter(|t| *t == Self::Promo);
+
+        from_prefix
+            .or_else(from_label)
+            .or_else(from_title)
+            .unwrap_or_default()
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct TitleParts {
-    prefix: Option<String>,
-    title: String,
-    label: Option<String>,
+    pub prefix: Option<String>,
+    pub title: String,
+    pub label: Option<String>,
 }
 
 impl Pack {
     pub fn new(element: ElementRef) -> Result<Self> {
+        let id = element
+            .attr("value")
+            .ok_or_else(|| VegaError::selector_missing_attr("option", "value"))?;
         let raw_title = Self::flatten_title(&element.inner_html())?;
-        let title_parts = Self::process_title_parts(&raw_title)?;
+
+        Self::from_title(id, &raw_title)
+    }
+
+    fn from_title(id: &str, raw_title: &str) -> Result<Self> {
+        let title_parts = Self::process_title_parts(raw_title)?;
+
+        let product_type = ProductType::infer(&title_parts);
+        let label = title_parts.label.as_deref();
+        let set_code = label.and_then(Self::normalize_set_code);
+        let set_number = label
+            .and_then(Self::split_set_code)
+            .and_then(|(_, number)| number.parse().ok());
 
         Ok(Self {
-            id: element.attr("value").unwrap().to_string(),
-            raw_title,
+            id: id.to_string(),
+            raw_title: raw_title.to_string(),
             title_parts,
+            product_type,
+            set_code,
+            set_number,
         })
     }
 
+    /// Splits a label like `OP07` or `ST-15` into its series letters and set number.
+    fn split_set_code(label: &str) -> Option<(String, String)> {
+        static SET_CODE_REG: OnceLock<Regex> = OnceLock::new();
+        let reg = SET_CODE_REG.get_or_init(|| Regex::new(r"^([A-Za-z]+)-?(\d+)$").unwrap());
+        let captured = reg.captures(label.trim())?;
+
+        Some((captured[1].to_uppercase(), captured[2].to_string()))
+    }
+
+    /// Set code written the same way in every pack, e.g. `OP-07` for both `OP07` and `OP-07`.
+    pub fn normalize_set_code(label: &str) -> Option<String> {
+        let (series, number) = Self::split_set_code(label)?;
+        Some(format!("{}-{}", series, number))
+    }
+
+    /// Sorts packs by the release date of their product, packs without a known date last, and
+    /// then by product line.
+    pub fn sort_by_release(packs: &mut [Pack], products: &[ProductInfo]) {
+        let release_date = |pack: &Pack| {
+            products
+                .iter()
+                .find(|product| product.pack_id == pack.id)
+                .and_then(|product| product.release_date)
+        };
+
+        packs.sort_by(|a, b| {
+            let (a_date, b_date) = (release_date(a), release_date(b));
+            (a_date.is_none(), a_date)
+                .cmp(&(b_date.is_none(), b_date))
+                .then_with(|| a.product_line_cmp(b))
+        });
+    }
+
+    /// Orders packs by product line and then by set number, packs without a set number last.
+    pub fn product_line_cmp(&self, other: &Pack) -> Ordering {
+        let key = |pack: &Pack| {
+            (
+                pack.product_type,
+                pack.set_number.is_none(),
+                pack.set_number,
+                pack.id.clone(),
+            )
+        };
+
+        key(self).cmp(&key(other))
+    }
+
     fn process_title_parts(raw_title: &str) -> Result<TitleParts> {
         let mut processed_title: String = raw_title.to_string();
 
@@ -117,6 +257,7 @@ impl fmt::Display for Pack {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::NaiveDate;
 
     #[test]
     fn process_title_parts_returns_all_parts() {
@@ -217,4 +358,132 @@ mod tests {
         let result = Pack::flatten_title(title);
         assert_eq!(result.unwrap(), title);
     }
+
+    fn get_test_title_parts(prefix: Option<&str>, title: &str, label: Option<&str>) -> TitleParts {
+        TitleParts {
+            prefix: prefix.map(String::from),
+            title: title.to_string(),
+            label: label.map(String::from),
+        }
+    }
+
+    #[test]
+    fn split_set_code_inserts_dash() {
+        let expected = Some((String::from("OP"), String::from("07")));
+        assert_eq!(Pack::split_set_code("OP07"), expected);
+        assert_eq!(Pack::split_set_code("OP-07"), expected);
+    }
+
+    #[test]
+    fn split_set_code_not_a_code_returns_none() {
+        assert_eq!(Pack::split_set_code("OP01-OP02"), None);
+        assert_eq!(Pack::split_set_code("Facts"), None);
+    }
+
+    #[test]
+    fn from_title_derives_set_metadata() {
+        let pack =
+            Pack::from_title("569107", "BOOSTER PACK -500 YEARS IN THE FUTURE- [OP07]").unwrap();
+
+        assert_eq!(pack.product_type, ProductType::Booster);
+        assert_eq!(pack.set_code.as_deref(), Some("OP-07"));
+        assert_eq!(pack.set_number, Some(7));
+    }
+
+    #[test]
+    fn deserialize_reads_release_order_of_older_versions() {
+        let pack: Pack = serde_json::from_str(
+            r#"{"id":"569107","raw_title":"BOOSTER PACK -500 YEARS IN THE FUTURE- [OP07]",
+                "title_parts":{"prefix":"BOOSTER PACK","title":"500 YEARS IN THE FUTURE",
+                               "label":"OP07"},
+                "product_type":"Booster","set_code":"OP-07","release_order":7}"#,
+        )
+        .unwrap();
+
+        assert_eq!(pack.set_number, Some(7));
+    }
+
+    #[test]
+    fn infer_product_type_from_prefix() {
+        let cases = [
+            ("BOOSTER PACK", ProductType::Booster),
+            ("STARTER DECK", ProductType::StarterDeck),
+            ("EXTRA BOOSTER", ProductType::ExtraBooster),
+            ("PREMIUM BOOSTER", ProductType::PremiumBooster),
+        ];
+
+        for (prefix, expected) in cases {
+            let title_parts = get_test_title_parts(Some(prefix), "TITLE", None);
+            assert_eq!(ProductType::infer(&title_parts), expected, "{}", prefix);
+        }
+    }
+
+    #[test]
+    fn infer_product_type_unknown_prefix_uses_set_code() {
+        let title_parts = get_test_title_parts(Some("ブースターパック"), "TITLE", Some("OP-07"));
+        assert_eq!(ProductType::infer(&title_parts), ProductType::Booster);
+
+        let title_parts = get_test_title_parts(Some("ULTRA DECK"), "TITLE", Some("ST10"));
+        assert_eq!(ProductType::infer(&title_parts), ProductType::StarterDeck);
+    }
+
+    #[test]
+    fn infer_product_type_promo_title() {
+        let title_parts = get_test_title_parts(None, "Promotion card", None);
+        assert_eq!(ProductType::infer(&title_parts), ProductType::Promo);
+    }
+
+    #[test]
+    fn infer_product_type_returns_other() {
+        let title_parts = get_test_title_parts(None, "Other Product Card", None);
+        assert_eq!(ProductType::infer(&title_parts), ProductType::Other);
+    }
+
+    #[test]
+    fn product_line_cmp_sorts_by_product_line_and_number() {
+        let pack = |id: &str, raw_title: &str| Pack::from_title(id, raw_title).unwrap();
+
+        let mut packs = [
+            pack("1", "STARTER DECK -Straw Hat Crew- [ST01]"),
+            pack("2", "BOOSTER PACK -AWAKENING OF THE NEW ERA- [OP05]"),
+            pack("3", "BOOSTER PACK -ROMANCE DAWN- [OP01]"),
+        ];
+        packs.sort_by(|a, b| a.product_line_cmp(b));
+
+        let ids: Vec<&str> = packs.iter().map(|pack| pack.id.as_str()).collect();
+        assert_eq!(ids, vec!["3", "2", "1"]);
+    }
+
+    #[test]
+    fn sort_by_release_uses_product_release_dates() {
+        let pack = |id: &str, raw_title: &str| Pack::from_title(id, raw_title).unwrap();
+        let product = |pack_id: &str, release_date: Option<(i32, u32, u32)>| ProductInfo {
+            pack_id: pack_id.to_string(),
+            title: String::new(),
+            release_date: release_date.and_then(|(y, m, d)| NaiveDate::from_ymd_opt(y, m, d)),
+            img_url: None,
+            img_full_url: None,
+            price: None,
+            pack_size: None,
+        };
+
+        let mut packs = [
+            pack("1", "BOOSTER PACK -ROMANCE DAWN- [OP01]"),
+            pack("2", "STARTER DECK -Straw Hat Crew- [ST01]"),
+            pack("3", "BOOSTER PACK -AWAKENING OF THE NEW ERA- [OP05]"),
+            pack("4", "STARTER DECK -Zoro & Sanji- [ST12]"),
+            pack("5", "BOOSTER PACK -PARAMOUNT WAR- [OP02]"),
+        ];
+        let products = [
+            product("1", Some((2022, 12, 2))),
+            product("2", Some((2022, 12, 2))),
+            product("3", Some((2023, 12, 8))),
+            product("4", Some((2023, 6, 30))),
+            product("5", None),
+        ];
+        Pack::sort_by_release(&mut packs, &products);
+
+        let ids: Vec<&str> = packs.iter().map(|pack| pack.id.as_str()).collect();
+        assert_eq!(ids, vec!["1", "2", "4", "3", "5"]);
+    }
 }
@@ -0,0 +1,286 @@
+use anyhow::Error;
+use chrono::{DateTime, Utc};
+use log::trace;
+use serde::{Deserialize, Serialize};
+use std::{
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// How politely requests are sent to the official site, set under `[request]` in locale files.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct RequestPolicy {
+    /// Minimum delay between the start of two consecutive requests
+    pub min_delay_ms: u64,
+    /// Total number of attempts per request, including the first one
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled after every failed attempt
+    pub initial_backoff_ms: u64,
+    /// Longest delay computed by the exponential backoff
+    pub max_backoff_ms: u64,
+    /// Longest `Retry-After` honoured, a site asking to wait more makes the request fail
+    pub max_retry_after_ms: u64,
+}
+
+impl Default for RequestPolicy {
+    fn default() -> Self {
+        RequestPolicy {
+            min_delay_ms: 0,
+            max_attempts: 3,
+            initial_backoff_ms: 1000,
+            max_backoff_ms: 30000,
+            max_retry_after_ms: 300000,
+        }
+    }
+}
+
+/// Values given on the command line, taking precedence over the locale configuration.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RequestOverrides {
+    pub min_delay_ms: Option<u64>,
+    pub max_attempts: Option<u32>,
+    pub initial_backoff_ms: Option<u64>,
+    pub max_backoff_ms: Option<u64>,
+}
+
+impl RequestPolicy {
+    pub fn with_overrides(mut self, overrides: RequestOverrides) -> Self {
+        if let Some(min_delay_ms) = overrides.min_delay_ms {
+            self.min_delay_ms = min_delay_ms;
+        }
+
+        if let Some(max_attempts) = overrides.max_attempts {
+            self.max_attempts = max_attempts;
+        }
+
+        if let Some(initial_backoff_ms) = overrides.initial_backoff_ms {
+            self.initial_backoff_ms = initial_backoff_ms;
+        }
+
+        if let Some(max_backoff_ms) = overrides.max_backoff_ms {
+            self.max_backoff_ms = max_backoff_ms;
+        }
+
+        self
+    }
+
+    pub fn min_delay(&self) -> Duration {
+        Duration::from_millis(self.min_delay_ms)
+    }
+
+    /// Delay to wait after the given failed attempt (starting at 1) before trying again, `None`
+    /// when `Retry-After` asks for more than `max_retry_after_ms`.
+    pub fn retry_delay(&self, attempt: u32, retry_after: Option<Duration>) -> Option<Duration> {
+        let factor = 2u64.saturating_pow(attempt.saturating_sub(1));
+        let backoff = self
+            .initial_backoff_ms
+            .saturating_mul(factor)
+            .min(self.max_backoff_ms);
+        let backoff = Duration::from_millis(backoff);
+
+        match retry_after {
+            Some(retry_after) if retry_after > Duration::from_millis(self.max_retry_after_ms) => {
+                None
+            }
+            Some(retry_after) => Some(retry_after.max(backoff)),
+            None => Some(backoff),
+        }
+    }
+}
+
+pub fn is_retryable_status(status: u16) -> bool {
+    status == 429 || (500..600).contains(&status)
+}
+
+/// Timeouts, refused or reset connections and truncated bodies are worth retrying.
+pub fn is_transient_error(error: &Error) -> bool {
+    error.chain().any(|cause| {
+        if let Some(err) = cause.downcast_ref::<reqwest::Error>() {
+            return err.is_timeout() || err.is_connect() || err.is_request() || err.is_body();
+        }
+
+        if let Some(err) = cause.downcast_ref::<std::io::Error>() {
+            return matches!(
+                err.kind(),
+                std::io::ErrorKind::TimedOut
+                    | std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+                    | std::io::ErrorKind::ConnectionRefused
+                    | std::io::ErrorKind::UnexpectedEof
+            );
+        }
+
+        false
+    })
+}
+
+/// Parses a `Retry-After` header, given either in seconds or as an HTTP date.
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    let delay = date.with_timezone(&Utc) - Utc::now();
+    Some(delay.to_std().unwrap_or(Duration::ZERO))
+}
+
+/// Spaces out requests sent from any thread by at least the policy's minimum delay.
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    next_slot: Mutex<Option<Instant>>,
+}
+
+impl RateLimiter {
+    pub fn wait(&self, min_delay: Duration) {
+        if min_delay.is_zero() {
+            return;
+        }
+
+        let wait_until = {
+            let mut next_slot = self.next_slot.lock().unwrap();
+            let now = Instant::now();
+            let slot = match *next_slot {
+                Some(slot) if slot > now => slot,
+                _ => now,
+            };
+
+            *next_slot = Some(slot + min_delay);
+            slot
+        };
+
+        let delay = wait_until.saturating_duration_since(Instant::now());
+        if !delay.is_zero() {
+            trace!("rate limiting, waiting {:?}", delay);
+            thread::sleep(delay);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_delay_doubles_until_max() {
+        let policy = RequestPolicy {
+            initial_backoff_ms: 100,
+            max_backoff_ms: 350,
+            ..RequestPolicy::default()
+        };
+
+        assert_eq!(
+            policy.retry_delay(1, None),
+            Some(Duration::from_millis(100))
+        );
+        assert_eq!(
+            policy.retry_delay(2, None),
+            Some(Duration::from_millis(200))
+        );
+        assert_eq!(
+            policy.retry_delay(3, None),
+            Some(Duration::from_millis(350))
+        );
+        assert_eq!(
+            policy.retry_delay(40, None),
+            Some(Duration::from_millis(350))
+        );
+    }
+
+    #[test]
+    fn retry_delay_respects_longer_retry_after() {
+        let policy = RequestPolicy {
+            initial_backoff_ms: 100,
+            ..RequestPolicy::default()
+        };
+
+        let retry_after = Some(Duration::from_secs(5));
+        assert_eq!(
+            policy.retry_delay(1, retry_after),
+            Some(Duration::from_secs(5))
+        );
+    }
+
+    #[test]
+    fn retry_delay_honours_retry_after_above_max_backoff() {
+        let policy = RequestPolicy {
+            initial_backoff_ms: 100,
+            max_backoff_ms: 30000,
+            max_retry_after_ms: 300000,
+            ..RequestPolicy::default()
+        };
+
+        let retry_after = Some(Duration::from_secs(120));
+        assert_eq!(
+            policy.retry_delay(1, retry_after),
+            Some(Duration::from_secs(120))
+        );
+    }
+
+    #[test]
+    fn retry_delay_longer_than_max_retry_after_returns_none() {
+        let policy = RequestPolicy {
+            max_retry_after_ms: 300000,
+            ..RequestPolicy::default()
+        };
+
+        assert_eq!(
+            policy.retry_delay(1, Some(Duration::from_secs(86400))),
+            None
+        );
+
+        let retry_after = parse_retry_after("Fri, 31 Dec 9999 23:59:59 GMT");
+        assert_eq!(policy.retry_delay(1, retry_after), None);
+    }
+
+    #[test]
+    fn with_overrides_replaces_only_given_values() {
+        let overrides = RequestOverrides {
+            max_attempts: Some(7),
+            max_backoff_ms: Some(5000),
+            ..RequestOverrides::default()
+        };
+
+        let policy = RequestPolicy::default().with_overrides(overrides);
+        assert_eq!(policy.max_attempts, 7);
+        assert_eq!(policy.max_backoff_ms, 5000);
+        assert_eq!(policy.min_delay_ms, RequestPolicy::default().min_delay_ms);
+    }
+
+    #[test]
+    fn is_retryable_status_matches_5xx_and_429() {
+        assert!(is_retryable_status(500));
+        assert!(is_retryable_status(503));
+        assert!(is_retryable_status(429));
+        assert!(!is_retryable_status(200));
+        assert!(!is_retryable_status(404));
+    }
+
+    #[test]
+    fn is_transient_error_matches_connection_reset() {
+        let error = Error::new(std::io::Error::from(std::io::ErrorKind::ConnectionReset));
+        assert!(is_transient_error(&error));
+
+        let error = anyhow::anyhow!("selector not found");
+        assert!(!is_transient_error(&error));
+    }
+
+    #[test]
+    fn parse_retry_after_seconds() {
+        assert_eq!(parse_retry_after("120"), Some(Duration::from_secs(120)));
+    }
+
+    #[test]
+    fn parse_retry_after_past_date_is_zero() {
+        let delay = parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT");
+        assert_eq!(delay, Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn parse_retry_after_invalid_returns_none() {
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+}
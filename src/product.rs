@@ -0,0 +1,280 @@
+use anyhow::Result;
+use chrono::NaiveDate;
+use log::{trace, warn};
+use regex::Regex;
+use scraper::{ElementRef, Selector};
+use serde::{Deserialize, Serialize};
+use std::{fmt, sync::OnceLock};
+
+use crate::{error::VegaError, pack::Pack, selectors::Selectors};
+
+/// Product of the official products page matched to the pack of the cardlist it contains.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ProductInfo {
+    pub pack_id: String,
+    pub title: String,
+    pub release_date: Option<NaiveDate>,
+    pub img_url: Option<String>, // As found in the page, usually relative to the hostname
+    pub img_full_url: Option<String>,
+    pub price: Option<String>, // MSRP as shown by the site, e.g. `$3.99` or `220円(税込)`
+    pub pack_size: Option<String>, // e.g. `12 cards per pack`
+}
+
+/// Selectors of the products page entries, parsed once for the whole page.
+pub struct ProductSelectors<'a> {
+    config: &'a Selectors,
+    link: Selector,
+    title: Selector,
+    release_date: Selector,
+    img_url: Selector,
+    price: Selector,
+    pack_size: Selector,
+}
+
+impl<'a> ProductSelectors<'a> {
+    pub fn parse(config: &'a Selectors) -> Result<Self> {
+        Ok(ProductSelectors {
+            link: Self::parse_one("a[href]")?,
+            title: Self::parse_one(&config.product_title)?,
+            release_date: Self::parse_one(&config.product_release_date)?,
+            img_url: Self::parse_one(&config.product_img_url)?,
+            price: Self::parse_one(&config.product_price)?,
+            pack_size: Self::parse_one(&config.product_pack_size)?,
+            config,
+        })
+    }
+
+    fn parse_one(selector: &str) -> Result<Selector> {
+        let parsed = Selector::parse(selector).map_err(|e| VegaError::Selector {
+            selector: selector.to_string(),
+            problem: format!("is invalid: {:?}", e),
+        })?;
+
+        Ok(parsed)
+    }
+}
+
+impl ProductInfo {
+    /// Reads an entry of the products page, returning `None` when it matches no pack.
+    pub fn new(
+        selectors: &ProductSelectors,
+        element: ElementRef,
+        packs: &[Pack],
+    ) -> Result<Option<Self>> {
+        let title = Self::get_text(element, &selectors.title)
+            .ok_or_else(|| VegaError::selector_missing(&selectors.config.product_title))?;
+
+        let Some(pack) = Self::match_pack(selectors, element, &title, packs) else {
+            trace!("product `{}` matches no pack", title);
+            return Ok(None);
+        };
+
+        let release_date = match Self::get_text(element, &selectors.release_date) {
+            Some(raw_date) => {
+                let release_date = Self::parse_release_date(&raw_date);
+                if release_date.is_none() {
+                    warn!("unknown release date format for `{}`: {}", title, raw_date);
+                }
+
+                release_date
+            }
+            None => None,
+        };
+
+        let img_url = element
+            .select(&selectors.img_url)
+            .next()
+            .and_then(|img| img.value().attr("src"))
+            .map(String::from);
+
+        Ok(Some(ProductInfo {
+            pack_id: pack.id.clone(),
+            release_date,
+            img_url,
+            img_full_url: None,
+            price: Self::get_text(element, &selectors.price),
+            pack_size: Self::get_text(element, &selectors.pack_size),
+            title,
+        }))
+    }
+
+    /// Pack the entry links to in the cardlist, or else the one sharing the set code of its
+    /// title.
+    fn match_pack<'a>(
+        selectors: &ProductSelectors,
+        element: ElementRef,
+        title: &str,
+        packs: &'a [Pack],
+    ) -> Option<&'a Pack> {
+        static SERIES_REG: OnceLock<Regex> = OnceLock::new();
+        let series_reg = SERIES_REG.get_or_init(|| Regex::new(r"[?&]series=(\d+)").unwrap());
+        let linked_pack = std::iter::once(element)
+            .chain(element.select(&selectors.link))
+            .filter_map(|link| link.value().attr("href"))
+            .filter_map(|href| series_reg.captures(href))
+            .find_map(|captured| packs.iter().find(|pack| pack.id == captured[1]));
+
+        if linked_pack.is_some() {
+            return linked_pack;
+        }
+
+        static LABEL_REG: OnceLock<Regex> = OnceLock::new();
+        let label_reg = LABEL_REG.get_or_init(|| Regex::new(r"\[(.*?)\]").unwrap());
+        let set_code = Pack::normalize_set_code(&label_reg.captures(title)?[1])?;
+        packs
+            .iter()
+            .find(|pack| pack.set_code.as_deref() == Some(set_code.as_str()))
+    }
+
+    /// Finds the date in texts like `Release Date: December 2, 2022`, `2022/12/02` or
+    /// `2022年12月2日`.
+    pub fn parse_release_date(value: &str) -> Option<NaiveDate> {
+        static NUMERIC_REG: OnceLock<Regex> = OnceLock::new();
+        let numeric_reg = NUMERIC_REG.get_or_init(|| {
+            Regex::new(r"(\d{4})\s*[./\-年]\s*(\d{1,2})\s*[./\-月]\s*(\d{1,2})").unwrap()
+        });
+        if let Some(captured) = numeric_reg.captures(value) {
+            return NaiveDate::from_ymd_opt(
+                captured[1].parse().ok()?,
+                captured[2].parse().ok()?,
+                captured[3].parse().ok()?,
+            );
+        }
+
+        static ENGLISH_REG: OnceLock<Regex> = OnceLock::new();
+        let english_reg = ENGLISH_REG
+            .get_or_init(|| Regex::new(r"([A-Za-z]{3,9})\.?\s+(\d{1,2}),?\s+(\d{4})").unwrap());
+        let captured = english_reg.captures(value)?;
+        let date = format!("{} {} {}", &captured[1], &captured[2], &captured[3]);
+
+        NaiveDate::parse_from_str(&date, "%B %d %Y")
+            .or_else(|_| NaiveDate::parse_from_str(&date, "%b %d %Y"))
+            .ok()
+    }
+
+    /// Text of the first node matching `selector` with whitespace collapsed, `None` when missing
+    /// or empty.
+    fn get_text(element: ElementRef, selector: &Selector) -> Option<String> {
+        let node = element.select(selector).next()?;
+        let text = node.text().collect::<Vec<_>>().join(" ");
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+
+        if text.is_empty() {
+            return None;
+        }
+
+        Some(text)
+    }
+}
+
+impl fmt::Display for ProductInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.pack_id, self.title)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scraper::Html;
+
+    fn get_test_packs() -> Vec<Pack> {
+        serde_json::from_str(
+            r#"[{"id":"569101","raw_title":"BOOSTER PACK -ROMANCE DAWN- [OP01]",
+                 "title_parts":{"prefix":"BOOSTER PACK","title":"ROMANCE DAWN","label":"OP01"},
+                 "product_type":"Booster","set_code":"OP-01","set_number":1},
+                {"id":"569001","raw_title":"STARTER DECK -Straw Hat Crew- [ST01]",
+                 "title_parts":{"prefix":"STARTER DECK","title":"Straw Hat Crew","label":"ST01"},
+                 "product_type":"StarterDeck","set_code":"ST-01","set_number":1}]"#,
+        )
+        .unwrap()
+    }
+
+    fn parse_product(html: &str) -> Option<ProductInfo> {
+        let document = Html::parse_fragment(html);
+        let selectors = Selectors::default();
+        let element = document
+            .select(&Selector::parse(&selectors.products).unwrap())
+            .next()
+            .unwrap();
+
+        let product_selectors = ProductSelectors::parse(&selectors).unwrap();
+        ProductInfo::new(&product_selectors, element, &get_test_packs()).unwrap()
+    }
+
+    #[test]
+    fn parse_release_date_english() {
+        let expected = NaiveDate::from_ymd_opt(2022, 12, 2);
+        assert_eq!(
+            ProductInfo::parse_release_date("Release Date: December 2, 2022"),
+            expected
+        );
+        assert_eq!(ProductInfo::parse_release_date("Dec. 2, 2022"), expected);
+    }
+
+    #[test]
+    fn parse_release_date_numeric() {
+        let expected = NaiveDate::from_ymd_opt(2022, 7, 8);
+        assert_eq!(ProductInfo::parse_release_date("2022/07/08"), expected);
+        assert_eq!(ProductInfo::parse_release_date("2022.7.8 (Fri)"), expected);
+        assert_eq!(
+            ProductInfo::parse_release_date("発売日：2022年7月8日"),
+            expected
+        );
+    }
+
+    #[test]
+    fn parse_release_date_unknown_returns_none() {
+        assert_eq!(ProductInfo::parse_release_date("Coming soon"), None);
+        assert_eq!(ProductInfo::parse_release_date("2022/13/40"), None);
+    }
+
+    #[test]
+    fn new_matches_pack_by_link() {
+        let product = parse_product(
+            r#"<div class="productsList"><div class="productsCol">
+                <a href="../cardlist/?series=569001">Card list</a>
+                <h3 class="productsTtl">STARTER DECK -Straw Hat Crew-</h3>
+            </div></div>"#,
+        )
+        .unwrap();
+
+        assert_eq!(product.pack_id, "569001");
+        assert_eq!(product.release_date, None);
+    }
+
+    #[test]
+    fn new_matches_pack_by_set_code() {
+        let product = parse_product(
+            r#"<div class="productsList"><div class="productsCol">
+                <img src="../images/products/boosters/op01/img_thumbnail.png">
+                <h3 class="productsTtl">BOOSTER PACK<br> -ROMANCE DAWN- [OP-01]</h3>
+                <dl><dt>Release Date</dt><dd class="productsDate">December 2, 2022</dd></dl>
+                <dl><dt>Price</dt><dd class="productsPrice">$3.99</dd></dl>
+                <dl><dt>Contents</dt><dd class="productsSize">12 cards per pack</dd></dl>
+            </div></div>"#,
+        )
+        .unwrap();
+
+        assert_eq!(product.pack_id, "569101");
+        assert_eq!(product.title, "BOOSTER PACK -ROMANCE DAWN- [OP-01]");
+        assert_eq!(product.release_date, NaiveDate::from_ymd_opt(2022, 12, 2));
+        assert_eq!(
+            product.img_url.as_deref(),
+            Some("../images/products/boosters/op01/img_thumbnail.png")
+        );
+        assert_eq!(product.price.as_deref(), Some("$3.99"));
+        assert_eq!(product.pack_size.as_deref(), Some("12 cards per pack"));
+    }
+
+    #[test]
+    fn new_unknown_product_returns_none() {
+        let product = parse_product(
+            r#"<div class="productsList"><div class="productsCol">
+                <h3 class="productsTtl">OFFICIAL CARD SLEEVE</h3>
+            </div></div>"#,
+        );
+
+        assert_eq!(product, None);
+    }
+}
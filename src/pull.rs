@@ -0,0 +1,337 @@
+use std::{
+    io::{self, Write},
+    path::Path,
+    sync::Mutex,
+    time::Instant,
+};
+
+use anyhow::{bail, Result};
+use chrono::Utc;
+use log::{error, info, warn};
+
+use crate::{
+    cli::LanguageCode,
+    download,
+    fetcher::Fetcher,
+    localizer::Localizer,
+    manifest::{ImageEntry, Manifest, PackEntry},
+    policy::RequestOverrides,
+    product::ProductInfo,
+    scraper::{CardFailure, OpTcgScraper},
+    storage::{DataStore, StoreLocation},
+};
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PullOptions {
+    pub download_images: bool,
+    /// Keep existing data and only fetch what is missing or changed, resuming interrupted runs
+    pub sync: bool,
+    /// Maximum number of images downloaded in parallel
+    pub jobs: usize,
+    pub request: RequestOverrides,
+    /// Skip cards that cannot be scraped instead of failing the whole pack
+    pub lenient: bool,
+}
+
+pub fn pull_all(
+    config_dir: &Path,
+    fetcher: &dyn Fetcher,
+    language: LanguageCode,
+    data_dir: &Path,
+    options: PullOptions,
+) -> Result<()> {
+    let localizer = Localizer::load(config_dir, language)?;
+    let policy = localizer.request.clone().with_overrides(options.request);
+    let scraper = OpTcgScraper::new(&localizer, fetcher)
+        .with_policy(policy)
+        .with_lenient(options.lenient);
+    let store = DataStore::new(data_dir, language);
+
+    let mut manifest = store.load_manifest()?;
+    let locale = manifest.locale_mut(language);
+    if !options.sync {
+        locale.sync_started_at = None;
+    }
+
+    let (started_at, resuming) = locale.begin_sync();
+    if resuming {
+        println!("Resuming interrupted sync started at {}", started_at);
+    }
+
+    store.write_manifest(&manifest)?;
+
+    println!("\nFetching packs ({:?})...", language);
+    let start = Instant::now();
+
+    let packs = scraper.fetch_all_packs()?;
+    let packs_hash = Manifest::hash(&serde_json::to_vec(&packs)?);
+
+    let locale = manifest.locale_mut(language);
+    if options.sync
+        && locale.packs_hash.as_ref() == Some(&packs_hash)
+        && store.get_path(StoreLocation::PacksListFile)?.exists()
+    {
+        info!("packs are unchanged, keeping existing file");
+    } else {
+        store.write_packs(&packs)?;
+        locale.packs_hash = Some(packs_hash);
+    }
+
+    locale
+        .packs
+        .retain(|pack_id, _| packs.iter().any(|pack| &pack.id == pack_id));
+
+    let duration = start.elapsed();
+    info!("fetching packs took: {:?}", duration);
+
+    println!("Successfully stored data for {} packs!", packs.len());
+
+    // Products only add release dates and prices, a site without a products page still gets
+    // its cards pulled
+    print!("Fetching products...");
+    io::stdout().flush()?;
+    match scraper.fetch_all_products(&packs) {
+        Ok(products) => {
+            store.write_products(&products)?;
+            println!(
+                " OK ({} of {} packs matched)\n",
+                products.len(),
+                packs.len()
+            );
+
+            if options.download_images {
+                download_pack_images(
+                    &scraper,
+                    &store,
+                    &mut manifest,
+                    language,
+                    &products,
+                    options,
+                )?;
+            }
+        }
+        Err(e) => {
+            warn!("failed to fetch products: {:#}", e);
+            println!(" FAILED ({:#})\n", e);
+        }
+    }
+
+    let start = Instant::now();
+    let mut failures = Vec::new();
+    for (idx, pack) in packs.iter().enumerate() {
+        let locale = manifest.locale_mut(language);
+
+        if resuming && locale.is_pack_synced_since(&pack.id, started_at) {
+            println!(
+                "[{}/{}] Pack `{}` already synced, skipping",
+                (idx + 1),
+                packs.len(),
+                pack.id
+            );
+            continue;
+        }
+
+        print!(
+            "[{}/{}] Fetching cards for pack `{}`...",
+            (idx + 1),
+            packs.len(),
+            pack.id
+        );
+        io::stdout().flush()?;
+
+        let cards = scraper.fetch_all_cards(&pack.id)?;
+        let skipped = scraper.take_failures();
+        if cards.is_empty() {
+            error!("no cards available for pack `{}`", &pack.id);
+            bail!("No cards found");
+        }
+
+        let status = if skipped.is_empty() {
+            String::from("OK")
+        } else {
+            format!("OK ({} skipped)", skipped.len())
+        };
+        failures.extend(skipped);
+
+        let content_hash = Manifest::hash(&serde_json::to_vec(&cards)?);
+        let cards_path = store.get_path(StoreLocation::CardsFile(&pack.id))?;
+
+        if options.sync && locale.is_pack_unchanged(&pack.id, &content_hash) && cards_path.exists()
+        {
+            info!("cards for `{}` are unchanged", pack.id);
+            println!(" UNCHANGED");
+        } else {
+            store.write_cards(&pack.id, &cards)?;
+            info!("fetched and wrote cards for: `{}`", pack.id);
+            println!(" {}", status);
+        }
+
+        let previous_images = locale
+            .packs
+            .remove(&pack.id)
+            .map(|entry| entry.images)
+            .unwrap_or_default();
+
+        let mut entry = PackEntry {
+            card_count: cards.len(),
+            content_hash,
+            fetched_at: Utc::now(),
+            images: previous_images,
+        };
+
+        if options.download_images {
+            let mut pending = Vec::new();
+            for card in cards.iter() {
+                let img_path = store.get_path(StoreLocation::ImageFile(card))?;
+                if options.sync
+                    && entry.is_image_unchanged(&card.id, &card.img_url)
+                    && img_path.exists()
+                {
+                    info!("image for `{}` is unchanged, skipping", card.id);
+                    continue;
+                }
+
+                pending.push(card.clone());
+            }
+
+            println!(
+                "Downloading {} images for pack `{}`...",
+                pending.len(),
+                pack.id
+            );
+
+            let downloaded = Mutex::new(Vec::new());
+            download::download_card_images(
+                &scraper,
+                &pending,
+                options.jobs,
+                |done, card, img_data| {
+                    let img_hash = Manifest::hash(&img_data.body);
+                    store.write_image(card, img_data)?;
+
+                    downloaded.lock().unwrap().push((
+                        card.id.clone(),
+                        ImageEntry {
+                            img_url: card.img_url.clone(),
+                            content_hash: img_hash,
+                            fetched_at: Utc::now(),
+                        },
+                    ));
+
+                    println!(
+                        "[{}/{}] Downloaded image for card `{}`",
+                        done,
+                        pending.len(),
+                        card.id
+                    );
+                    Ok(())
+                },
+            )?;
+
+            entry.images.extend(downloaded.into_inner().unwrap());
+        }
+
+        entry
+            .images
+            .retain(|card_id, _| cards.iter().any(|card| &card.id == card_id));
+
+        // Only stamp the pack once everything it needs is on disk, so a crash re-pulls it
+        entry.fetched_at = Utc::now();
+        manifest
+            .locale_mut(language)
+            .packs
+            .insert(pack.id.clone(), entry);
+        store.write_manifest(&manifest)?;
+    }
+
+    let duration = start.elapsed();
+    info!("fetching cards (and images) took: {:?}", duration);
+
+    manifest.locale_mut(language).end_sync();
+    store.write_manifest(&manifest)?;
+
+    if options.lenient {
+        store.write_failures(&failures)?;
+        CardFailure::print_summary(&failures);
+    }
+
+    Ok(())
+}
+
+/// Downloads the box art of every product to `images/packs/`, recording each image in the
+/// manifest once it is on disk.
+fn download_pack_images<F: Fetcher>(
+    scraper: &OpTcgScraper<F>,
+    store: &DataStore,
+    manifest: &mut Manifest,
+    language: LanguageCode,
+    products: &[ProductInfo],
+    options: PullOptions,
+) -> Result<()> {
+    let locale = manifest.locale_mut(language);
+    locale
+        .pack_images
+        .retain(|pack_id, _| products.iter().any(|product| &product.pack_id == pack_id));
+
+    let mut pending = Vec::new();
+    for product in products.iter() {
+        let Some(img_url) = &product.img_url else {
+            continue;
+        };
+
+        let img_path = store.get_path(StoreLocation::PackImageFile(product))?;
+        if options.sync
+            && locale.is_pack_image_unchanged(&product.pack_id, img_url)
+            && img_path.exists()
+        {
+            info!(
+                "image for pack `{}` is unchanged, skipping",
+                product.pack_id
+            );
+            continue;
+        }
+
+        pending.push(product.clone());
+    }
+
+    println!("Downloading {} pack images...", pending.len());
+
+    let downloaded = Mutex::new(Vec::new());
+    let result = download::download_pack_images(
+        scraper,
+        &pending,
+        options.jobs,
+        |done, product, img_data| {
+            let img_hash = Manifest::hash(&img_data.body);
+            store.write_pack_image(product, img_data)?;
+
+            downloaded.lock().unwrap().push((
+                product.pack_id.clone(),
+                ImageEntry {
+                    img_url: product.img_url.clone().unwrap_or_default(),
+                    content_hash: img_hash,
+                    fetched_at: Utc::now(),
+                },
+            ));
+
+            println!(
+                "[{}/{}] Downloaded image for pack `{}`",
+                done,
+                pending.len(),
+                product.pack_id
+            );
+            Ok(())
+        },
+    );
+
+    // Keep what was written before a failure so the next sync only retries the rest
+    manifest
+        .locale_mut(language)
+        .pack_images
+        .extend(downloaded.into_inner().unwrap());
+    store.write_manifest(manifest)?;
+    result?;
+
+    println!();
+    Ok(())
+}
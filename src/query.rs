@@ -0,0 +1,305 @@
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use log::{debug, info, warn};
+use std::{path::Path, time::Instant};
+
+use crate::{
+    card::{Card, CardCategory, CardColor, CardRarity},
+    cli::LanguageCode,
+    output::{self, OutputFormat},
+    storage::DataStore,
+};
+
+/// Inclusive range of values given as `N`, `MIN..MAX`, `MIN..` or `..MAX`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ValueRange {
+    pub min: Option<i32>,
+    pub max: Option<i32>,
+}
+
+impl ValueRange {
+    pub fn parse(value: &str) -> Result<ValueRange> {
+        let parse_bound = |bound: &str| -> Result<Option<i32>> {
+            let bound = bound.trim();
+            if bound.is_empty() {
+                return Ok(None);
+            }
+
+            let bound = bound
+                .parse::<i32>()
+                .with_context(|| format!("Invalid number `{}` in range `{}`", bound, value))?;
+            Ok(Some(bound))
+        };
+
+        let range = match value.split_once("..") {
+            Some((min, max)) => ValueRange {
+                min: parse_bound(min)?,
+                max: parse_bound(max)?,
+            },
+            None => {
+                let exact = parse_bound(value)?;
+                ValueRange {
+                    min: exact,
+                    max: exact,
+                }
+            }
+        };
+
+        if range.min.is_none() && range.max.is_none() {
+            bail!("Range `{}` has no bounds", value);
+        }
+
+        if let (Some(min), Some(max)) = (range.min, range.max) {
+            if min > max {
+                bail!("Range `{}` is empty", value);
+            }
+        }
+
+        Ok(range)
+    }
+
+    /// Cards without the value (e.g. the cost of a Leader) never match a range.
+    pub fn contains(&self, value: Option<i32>) -> bool {
+        let Some(value) = value else {
+            return false;
+        };
+
+        self.min.map_or(true, |min| value >= min) && self.max.map_or(true, |max| value <= max)
+    }
+}
+
+/// Criteria a card must meet to be part of the query results, all given criteria must match.
+#[derive(Args, Clone, Debug, Default)]
+pub struct CardFilter {
+    /// Only cards having one of these colors
+    #[arg(long = "color", value_delimiter = ',', value_parser = CardColor::from_str)]
+    pub colors: Vec<CardColor>,
+    /// Only cards of one of these categories
+    #[arg(long = "category", value_delimiter = ',', value_parser = CardCategory::from_str)]
+    pub categories: Vec<CardCategory>,
+    /// Only cards of one of these rarities (e.g. `super_rare`)
+    #[arg(long = "rarity", value_delimiter = ',', value_parser = CardRarity::from_str)]
+    pub rarities: Vec<CardRarity>,
+    /// Only cards with a cost in this range (`N`, `MIN..MAX`, `MIN..` or `..MAX`)
+    #[arg(long, value_name = "RANGE", value_parser = ValueRange::parse)]
+    pub cost: Option<ValueRange>,
+    /// Only cards with a power in this range (`N`, `MIN..MAX`, `MIN..` or `..MAX`)
+    #[arg(long, value_name = "RANGE", value_parser = ValueRange::parse)]
+    pub power: Option<ValueRange>,
+    /// Only cards with a type containing this text (case insensitive)
+    #[arg(long = "type", value_name = "TEXT")]
+    pub card_type: Option<String>,
+    /// Only cards with an effect containing this text (case insensitive)
+    #[arg(long, value_name = "TEXT")]
+    pub effect: Option<String>,
+    /// Only cards from these packs
+    #[arg(long = "pack", value_name = "PACK_ID", value_delimiter = ',')]
+    pub pack_ids: Vec<String>,
+}
+
+impl CardFilter {
+    pub fn matches(&self, card: &Card) -> bool {
+        if !self.colors.is_empty() && !card.colors.iter().any(|c| self.colors.contains(c)) {
+            return false;
+        }
+
+        if !self.categories.is_empty() && !self.categories.contains(&card.category) {
+            return false;
+        }
+
+        if !self.rarities.is_empty() && !self.rarities.contains(&card.rarity) {
+            return false;
+        }
+
+        if let Some(cost) = self.cost {
+            if !cost.contains(card.cost) {
+                return false;
+            }
+        }
+
+        if let Some(power) = self.power {
+            if !power.contains(card.power) {
+                return false;
+            }
+        }
+
+        if let Some(ref card_type) = self.card_type {
+            let card_type = card_type.to_lowercase();
+            if !card
+                .types
+                .iter()
+                .any(|t| t.to_lowercase().contains(&card_type))
+            {
+                return false;
+            }
+        }
+
+        if let Some(ref effect) = self.effect {
+            if !card.effect.to_lowercase().contains(&effect.to_lowercase()) {
+                return false;
+            }
+        }
+
+        if !self.pack_ids.is_empty() && !self.pack_ids.contains(&card.pack_id) {
+            return false;
+        }
+
+        true
+    }
+}
+
+/// Loads the cards previously pulled into `data_dir` and keeps the ones matching `filter`.
+pub fn find_cards(
+    data_dir: &Path,
+    language: LanguageCode,
+    filter: &CardFilter,
+) -> Result<Vec<Card>> {
+    let store = DataStore::new(data_dir, language);
+    let packs = store.load_packs()?;
+
+    for pack_id in filter.pack_ids.iter() {
+        if !packs.iter().any(|pack| &pack.id == pack_id) {
+            warn!("pack `{}` is not part of the pulled data", pack_id);
+        }
+    }
+
+    let mut matches = Vec::new();
+    for pack in packs.iter() {
+        if !filter.pack_ids.is_empty() && !filter.pack_ids.contains(&pack.id) {
+            continue;
+        }
+
+        let cards = store.load_cards(&pack.id)?;
+        let count = matches.len();
+        matches.extend(cards.into_iter().filter(|card| filter.matches(card)));
+        debug!(
+            "{} matching cards in pack `{}`",
+            matches.len() - count,
+            pack.id
+        );
+    }
+
+    Ok(matches)
+}
+
+pub fn query_cards(
+    data_dir: &Path,
+    language: LanguageCode,
+    filter: &CardFilter,
+    format: OutputFormat,
+    group_variants: bool,
+) -> Result<()> {
+    info!("querying cards in `{}`...", data_dir.display());
+    let start = Instant::now();
+
+    let cards = find_cards(data_dir, language, filter)?;
+    info!("found {} matching cards", cards.len());
+
+    output::print_cards(&cards, format, group_variants)?;
+
+    let duration = start.elapsed();
+    info!("query_cards took: {:?}", duration);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{card::test_util, pack::Pack};
+
+    #[test]
+    fn parse_exact_value_returns_ok() {
+        let range = ValueRange::parse("3").unwrap();
+        assert_eq!(range.min, Some(3));
+        assert_eq!(range.max, Some(3));
+    }
+
+    #[test]
+    fn parse_open_ranges_returns_ok() {
+        assert_eq!(
+            ValueRange::parse("5000..").unwrap(),
+            ValueRange {
+                min: Some(5000),
+                max: None
+            }
+        );
+        assert_eq!(
+            ValueRange::parse("..2").unwrap(),
+            ValueRange {
+                min: None,
+                max: Some(2)
+            }
+        );
+    }
+
+    #[test]
+    fn parse_invalid_ranges_returns_err() {
+        assert!(ValueRange::parse("..").is_err());
+        assert!(ValueRange::parse("5..2").is_err());
+        assert!(ValueRange::parse("two").is_err());
+    }
+
+    #[test]
+    fn contains_missing_value_returns_false() {
+        let range = ValueRange::parse("0..10").unwrap();
+        assert!(range.contains(Some(10)));
+        assert!(!range.contains(Some(11)));
+        assert!(!range.contains(None));
+    }
+
+    #[test]
+    fn matches_empty_filter_returns_true() {
+        assert!(CardFilter::default().matches(&test_util::card("OP01-016")));
+    }
+
+    #[test]
+    fn matches_every_criteria() {
+        let card = test_util::card("OP01-016");
+        let filter = CardFilter {
+            colors: vec![CardColor::Green, CardColor::Red],
+            categories: vec![CardCategory::Character],
+            rarities: vec![CardRarity::Rare],
+            cost: Some(ValueRange::parse("..2").unwrap()),
+            power: Some(ValueRange::parse("2000").unwrap()),
+            card_type: Some(String::from("straw hat")),
+            effect: Some(String::from("on play")),
+            pack_ids: vec![String::from("569101")],
+        };
+        assert!(filter.matches(&card));
+
+        let filter = CardFilter {
+            power: Some(ValueRange::parse("5000..").unwrap()),
+            ..filter
+        };
+        assert!(!filter.matches(&card));
+    }
+
+    #[test]
+    fn find_cards_reads_pulled_data() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+        let store = DataStore::new(dir, LanguageCode::English);
+
+        let pack: Pack = serde_json::from_str(
+            r#"{"id":"569101","raw_title":"BOOSTER PACK -ROMANCE DAWN- [OP01]",
+                "title_parts":{"prefix":"BOOSTER PACK","title":"ROMANCE DAWN","label":"OP01"}}"#,
+        )
+        .unwrap();
+        store.write_packs(&vec![pack]).unwrap();
+
+        let mut leader = test_util::card("OP01-001");
+        leader.category = CardCategory::Leader;
+        store
+            .write_cards("569101", &vec![leader, test_util::card("OP01-016")])
+            .unwrap();
+
+        let filter = CardFilter {
+            categories: vec![CardCategory::Character],
+            ..CardFilter::default()
+        };
+        let cards = find_cards(dir, LanguageCode::English, &filter).unwrap();
+
+        assert_eq!(cards.len(), 1);
+        assert_eq!(cards[0].id, "OP01-016");
+    }
+}
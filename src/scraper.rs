@@ -1,31 +1,107 @@
-use anyhow::{bail, Context, Result};
-use log::{debug, info};
-use reqwest::blocking::{Client, Response};
-use std::collections::HashMap;
+use anyhow::{anyhow, bail, Context, Result};
+use log::{debug, info, warn};
+use scraper::Html;
+use serde::Serialize;
+use std::{sync::Mutex, thread, time::Duration};
 
 use crate::{
     card::{Card, CardScraper},
+    error::{ScrapeLocation, VegaError},
+    fetcher::{FetchResponse, Fetcher},
     localizer::Localizer,
     pack::Pack,
+    policy::{self, RateLimiter, RequestPolicy},
+    product::{ProductInfo, ProductSelectors},
 };
 
-pub struct OpTcgScraper<'a> {
+/// Card left out of a pack in lenient mode, along with the markup that could not be scraped.
+#[derive(Clone, Debug, Serialize)]
+pub struct CardFailure {
+    pub pack_id: String,
+    pub card_id: String,
+    pub reason: String,
+    pub html: String,
+}
+
+impl CardFailure {
+    /// Lists the skipped cards on stderr, so it does not mix with data printed to stdout.
+    pub fn print_summary(failures: &[CardFailure]) {
+        if failures.is_empty() {
+            return;
+        }
+
+        eprintln!(
+            "Skipped {} card(s) that could not be scraped:",
+            failures.len()
+        );
+        for failure in failures {
+            eprintln!(
+                "  - {} [{}]: {}",
+                failure.card_id, failure.pack_id, failure.reason
+            );
+        }
+    }
+}
+
+pub struct OpTcgScraper<'a, F: Fetcher> {
     base_url: String,
     localizer: &'a Localizer,
+    fetcher: F,
+    policy: RequestPolicy,
+    rate_limiter: RateLimiter,
+    lenient: bool,
+    failures: Mutex<Vec<CardFailure>>,
 }
 
-impl<'a> OpTcgScraper<'a> {
-    pub fn new(localizer: &Localizer) -> OpTcgScraper {
+impl<'a, F: Fetcher> OpTcgScraper<'a, F> {
+    pub fn new(localizer: &'a Localizer, fetcher: F) -> OpTcgScraper<'a, F> {
         OpTcgScraper {
             base_url: localizer.hostname.clone(),
             localizer,
+            fetcher,
+            policy: localizer.request.clone(),
+            rate_limiter: RateLimiter::default(),
+            lenient: false,
+            failures: Mutex::new(Vec::new()),
         }
     }
 
+    pub fn with_policy(mut self, policy: RequestPolicy) -> Self {
+        debug!("using request policy: {:?}", policy);
+        self.policy = policy;
+        self
+    }
+
+    /// Skip cards that fail to be scraped instead of failing the whole pack, see
+    /// [`Self::take_failures`].
+    pub fn with_lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Cards skipped in lenient mode since the last call.
+    pub fn take_failures(&self) -> Vec<CardFailure> {
+        std::mem::take(&mut *self.failures.lock().unwrap())
+    }
+
     fn cardlist_endpoint(&self) -> String {
         format!("{}/{}", self.base_url, "cardlist")
     }
 
+    fn products_endpoint(&self) -> String {
+        format!("{}/{}", self.base_url, "products")
+    }
+
+    /// Absolute url of a link found in a page of the site.
+    fn get_full_url(&self, url: &str) -> String {
+        if url.contains("://") {
+            return url.to_string();
+        }
+
+        let path = url.trim_start_matches("../").trim_start_matches("./");
+        format!("{}/{}", self.base_url, path.trim_start_matches('/'))
+    }
+
     fn get_img_full_url(&self, img_url: &str) -> String {
         let short_img_url = &img_url[3..];
         let full_url = format!("{}/{}", self.base_url, short_img_url);
@@ -34,16 +110,87 @@ impl<'a> OpTcgScraper<'a> {
         full_url
     }
 
-    pub fn fetch_all_packs(&self) -> Result<Vec<Pack>> {
+    fn get(&self, url: &str, query: &[(&str, &str)]) -> Result<FetchResponse> {
+        let mut attempt = 1;
+        loop {
+            self.rate_limiter.wait(self.policy.min_delay());
+            let result = self.fetcher.get(url, query);
+
+            let retry_after = match result {
+                Ok(ref response) if policy::is_retryable_status(response.status) => response
+                    .header("retry-after")
+                    .and_then(policy::parse_retry_after),
+                Ok(ref response) => {
+                    debug!(
+                        "received {} ({})",
+                        response.status,
+                        response.content_type().unwrap_or("unknown content type")
+                    );
+
+                    if !(200..300).contains(&response.status) {
+                        bail!(VegaError::HttpStatus {
+                            url: url.to_string(),
+                            status: response.status,
+                        });
+                    }
+
+                    return result;
+                }
+                Err(ref e) if policy::is_transient_error(e) => None,
+                Err(_) => return result,
+            };
+
+            let error = match result {
+                Ok(response) => anyhow!(VegaError::HttpStatus {
+                    url: url.to_string(),
+                    status: response.status,
+                }),
+                Err(e) => e,
+            };
+
+            if attempt >= self.policy.max_attempts {
+                return Err(error.context(format!(
+                    "giving up on `{}` after {} attempt(s)",
+                    url, attempt
+                )));
+            }
+
+            let reason = format!("{:#}", error);
+
+            let Some(delay) = self.policy.retry_delay(attempt, retry_after) else {
+                return Err(error.context(format!(
+                    "giving up on `{}`, asked to retry after {:?} (more than the allowed {:?})",
+                    url,
+                    retry_after.unwrap_or_default(),
+                    Duration::from_millis(self.policy.max_retry_after_ms)
+                )));
+            };
+            warn!(
+                "attempt {}/{} for `{}` failed ({}), retrying in {:?}",
+                attempt, self.policy.max_attempts, url, reason, delay
+            );
+
+            thread::sleep(delay);
+            attempt += 1;
+        }
+    }
+
+    /// Cardlist page, listing the cards of `pack_id` if given.
+    pub fn fetch_cardlist_page(&self, pack_id: Option<&str>) -> Result<Html> {
         let url = self.cardlist_endpoint();
         info!("GET `{}`", url);
 
-        let response = reqwest::blocking::get(url)?.text()?;
+        let query: Vec<(&str, &str)> = pack_id.iter().map(|id| ("series", *id)).collect();
+        let response = self.get(&url, &query)?.text()?;
 
         info!("parsing HTML document");
-        let document = scraper::Html::parse_document(&response);
+        Ok(Html::parse_document(&response))
+    }
 
-        let sel = "div.seriesCol>select#series>option";
+    pub fn fetch_all_packs(&self) -> Result<Vec<Pack>> {
+        let document = self.fetch_cardlist_page(None)?;
+
+        let sel = &self.localizer.selectors.packs;
         info!("fetching series (packs) ({})...", sel);
 
         let series_selector = scraper::Selector::parse(sel).unwrap();
@@ -56,7 +203,7 @@ impl<'a> OpTcgScraper<'a> {
                         packs.push(pack);
                     }
                 }
-                Err(e) => bail!("failed to scrape data about packs: {}", e),
+                Err(e) => return Err(e.context(ScrapeLocation::packs())),
             }
         }
 
@@ -65,23 +212,9 @@ impl<'a> OpTcgScraper<'a> {
     }
 
     pub fn fetch_all_cards(&self, pack_id: &str) -> Result<Vec<Card>> {
-        let url = self.cardlist_endpoint();
-        info!("GET `{}`", url);
-
-        let mut params = HashMap::new();
-        params.insert("series", pack_id);
-
-        let client = Client::new();
-        let response = client
-            .get(self.cardlist_endpoint())
-            .query(&params)
-            .send()?
-            .text()?;
+        let document = self.fetch_cardlist_page(Some(pack_id))?;
 
-        info!("parsing HTML document");
-        let document = scraper::Html::parse_document(&response);
-
-        let sel = "div.resultCol>a";
+        let sel = &self.localizer.selectors.card_links;
         info!("fetching cards for pack `{}` ({})...", pack_id, sel);
 
         let card_ids_selector = scraper::Selector::parse(sel).unwrap();
@@ -90,20 +223,45 @@ impl<'a> OpTcgScraper<'a> {
         for element in document.select(&card_ids_selector) {
             let card_id = element
                 .attr("data-src")
-                .context("expected `data-src` attr on <a>")?
-                .to_string();
+                .map(|data_src| data_src.trim_start_matches('#'))
+                .filter(|card_id| !card_id.is_empty());
 
-            let card_id = &card_id[1..];
+            // A link without a card id is a card that cannot be scraped, not a broken pack
+            let result = match card_id {
+                Some(card_id) => {
+                    CardScraper::create_card(self.localizer, &document, card_id, pack_id)
+                }
+                None => Err(VegaError::selector_missing_attr(sel, "data-src").into()),
+            };
+            let card_id = card_id.unwrap_or_default();
 
-            match CardScraper::create_card(self.localizer, &document, card_id, pack_id) {
+            match result {
                 Ok(mut card) => {
                     debug!("computing img_full_url for card: {}", card);
                     card.img_full_url = Some(self.get_img_full_url(&card.img_url));
                     cards.push(card);
                 }
-                Err(e) => {
-                    bail!("failed to scrape data about card `{}`: {}", &card_id, e)
+                Err(e) if self.lenient => {
+                    warn!("skipping card `{}` of pack `{}`: {:#}", card_id, pack_id, e);
+                    let html = match card_id {
+                        "" => element.html(),
+                        _ => CardScraper::get_dl_node(self.localizer, &document, card_id)
+                            .map(|dl_elem| dl_elem.html())
+                            .unwrap_or_else(|_| element.html()),
+                    };
+                    debug!("markup of card `{}`: {}", card_id, html);
+
+                    self.failures.lock().unwrap().push(CardFailure {
+                        pack_id: pack_id.to_string(),
+                        card_id: card_id.to_string(),
+                        reason: format!("{:#}", e),
+                        html,
+                    });
+                }
+                Err(e) if card_id.is_empty() => {
+                    return Err(e.context(ScrapeLocation::pack(pack_id)))
                 }
+                Err(e) => return Err(e.context(ScrapeLocation::card(pack_id, card_id))),
             };
         }
 
@@ -111,11 +269,588 @@ impl<'a> OpTcgScraper<'a> {
         Ok(cards)
     }
 
-    pub fn download_card_image(&self, card: &Card) -> Result<Response> {
+    /// Release date and product info of `packs`, from the products page of the site.
+    pub fn fetch_all_products(&self, packs: &[Pack]) -> Result<Vec<ProductInfo>> {
+        let url = self.products_endpoint();
+        info!("GET `{}`", url);
+
+        let response = self.get(&url, &[])?.text()?;
+
+        info!("parsing HTML document");
+        let document = Html::parse_document(&response);
+
+        let selectors = &self.localizer.selectors;
+        info!("fetching products ({})...", selectors.products);
+
+        let products_selector = scraper::Selector::parse(&selectors.products).unwrap();
+        let product_selectors = ProductSelectors::parse(selectors)?;
+
+        let mut products: Vec<ProductInfo> = Vec::new();
+        for (idx, element) in document.select(&products_selector).enumerate() {
+            let product = ProductInfo::new(&product_selectors, element, packs)
+                .with_context(|| format!("failed to scrape product #{}", idx + 1))?;
+
+            let Some(mut product) = product else {
+                continue;
+            };
+
+            if products
+                .iter()
+                .any(|other| other.pack_id == product.pack_id)
+            {
+                debug!("pack `{}` already has a product, skipping", product.pack_id);
+                continue;
+            }
+
+            product.img_full_url = product.img_url.as_deref().map(|url| self.get_full_url(url));
+            debug!("processed product: `{}`", product);
+            products.push(product);
+        }
+
+        info!("matched {} products", products.len());
+        Ok(products)
+    }
+
+    pub fn download_card_image(&self, card: &Card) -> Result<FetchResponse> {
         let full_url = self.get_img_full_url(&card.img_url);
+        self.download_image(&full_url)
+    }
+
+    /// Downloads the box art of the pack `product` was matched to.
+    pub fn download_pack_image(&self, product: &ProductInfo) -> Result<FetchResponse> {
+        let img_url = product
+            .img_url
+            .as_deref()
+            .with_context(|| format!("no image for the product of pack `{}`", product.pack_id))?;
+
+        let full_url = self.get_full_url(img_url);
+        self.download_image(&full_url)
+    }
 
+    fn download_image(&self, full_url: &str) -> Result<FetchResponse> {
         debug!("downloading image `{}`...", full_url);
-        let response = reqwest::blocking::get(full_url)?;
+        let response = self.get(full_url, &[])?;
+
+        let content_type = response.content_type().unwrap_or_default();
+        if !content_type.starts_with("image/") {
+            bail!(
+                "expected an image for `{}` but got `{}`",
+                full_url,
+                content_type
+            );
+        }
+
         Ok(response)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        card::{variant::CardVariantKind, CardCategory, CardIllustration, CardRarity, CardVariant},
+        cli::LanguageCode,
+        fetcher::{FixtureFetcher, MemoryFetcher},
+        policy::RequestPolicy,
+    };
+    use std::{fs, path::Path};
+
+    const FIXTURES_DIR: &str = "tests/fixtures/en";
+
+    fn get_test_localizer() -> Localizer {
+        Localizer::load(Path::new("config"), LanguageCode::English).unwrap()
+    }
+
+    fn get_test_fetcher() -> FixtureFetcher {
+        FixtureFetcher::new(Path::new(FIXTURES_DIR)).unwrap()
+    }
+
+    #[test]
+    fn fetch_all_packs_returns_packs() {
+        let localizer = get_test_localizer();
+        let scraper = OpTcgScraper::new(&localizer, get_test_fetcher());
+
+        let packs = scraper.fetch_all_packs().unwrap();
+        let ids: Vec<_> = packs.iter().map(|pack| pack.id.as_str()).collect();
+
+        assert_eq!(ids, vec!["569101", "569001"]);
+        assert_eq!(packs[0].raw_title, "BOOSTER PACK -ROMANCE DAWN- [OP01]");
+    }
+
+    #[test]
+    fn fetch_all_cards_returns_cards() {
+        let localizer = get_test_localizer();
+        let scraper = OpTcgScraper::new(&localizer, get_test_fetcher());
+
+        let cards = scraper.fetch_all_cards("569101").unwrap();
+        assert_eq!(cards.len(), 3);
+
+        let leader = &cards[0];
+        assert_eq!(leader.id, "OP01-001");
+        assert_eq!(leader.pack_id, "569101");
+        assert_eq!(leader.rarity, CardRarity::Leader);
+        assert_eq!(leader.category, CardCategory::Leader);
+        assert_eq!(leader.power, Some(5000));
+        assert_eq!(leader.counter, None);
+        assert_eq!(
+            leader.img_full_url.as_deref(),
+            Some("https://en.onepiece-cardgame.com/images/cardlist/card/OP01-001.png?240322")
+        );
+
+        let event = &cards[2];
+        assert!(event.attributes.is_empty());
+        assert!(event.trigger.is_some());
+    }
+
+    #[test]
+    fn fetch_all_cards_returns_print_details() {
+        let localizer = get_test_localizer();
+        let scraper = OpTcgScraper::new(&localizer, get_test_fetcher());
+
+        let cards = scraper.fetch_all_cards("569101").unwrap();
+
+        let leader = &cards[0];
+        assert_eq!(leader.block, Some(1));
+        assert_eq!(leader.product.as_deref(), Some("-ROMANCE DAWN- [OP01]"));
+        assert_eq!(leader.illustration, Some(CardIllustration::Original));
+        assert_eq!(leader.illustrator_name.as_deref(), Some("Eiichiro Oda"));
+
+        // Not every entry shows the illustration details
+        let character = &cards[1];
+        assert_eq!(character.illustration, None);
+        assert_eq!(character.illustrator_name, None);
+    }
+
+    #[test]
+    fn fetch_all_cards_parses_variants() {
+        let localizer = get_test_localizer();
+        let scraper = OpTcgScraper::new(&localizer, get_test_fetcher());
+
+        let cards = scraper.fetch_all_cards("569001").unwrap();
+        assert_eq!(cards.len(), 3);
+
+        assert_eq!(cards[1].base_id, "ST01-012");
+        assert_eq!(cards[1].variant, None);
+
+        let parallel = &cards[2];
+        assert_eq!(parallel.id, "ST01-012_p1");
+        assert_eq!(parallel.base_id, "ST01-012");
+        assert_eq!(
+            parallel.variant,
+            Some(CardVariant {
+                kind: CardVariantKind::Parallel,
+                index: 1
+            })
+        );
+    }
+
+    #[test]
+    fn fetch_all_cards_unknown_pack_returns_err() {
+        let localizer = get_test_localizer();
+        let scraper = OpTcgScraper::new(&localizer, get_test_fetcher());
+
+        assert!(scraper.fetch_all_cards("000000").is_err());
+    }
+
+    #[test]
+    fn fetch_all_packs_from_memory_returns_packs() {
+        let localizer = get_test_localizer();
+        let mut fetcher = MemoryFetcher::new();
+        fetcher.insert_html(
+            &format!("{}/cardlist", localizer.hostname),
+            &[],
+            r#"<div class="seriesCol"><select id="series">
+                <option value="">ALL</option>
+                <option value="1">PREFIX -TITLE- [LABEL]</option>
+            </select></div>"#,
+        );
+
+        let scraper = OpTcgScraper::new(&localizer, fetcher);
+        let packs = scraper.fetch_all_packs().unwrap();
+
+        assert_eq!(packs.len(), 1);
+        assert_eq!(packs[0].id, "1");
+    }
+
+    struct FlakyFetcher {
+        failures: u16,
+        calls: std::sync::Mutex<u16>,
+    }
+
+    impl Fetcher for FlakyFetcher {
+        fn get(&self, _url: &str, _query: &[(&str, &str)]) -> Result<FetchResponse> {
+            let mut calls = self.calls.lock().unwrap();
+            *calls += 1;
+
+            if *calls <= self.failures {
+                let mut response = FetchResponse::new(503, "text/html", Vec::new());
+                response
+                    .headers
+                    .insert(String::from("retry-after"), String::from("0"));
+                return Ok(response);
+            }
+
+            Ok(FetchResponse::new(200, "image/png", b"\x89PNG".to_vec()))
+        }
+    }
+
+    fn get_test_policy() -> RequestPolicy {
+        RequestPolicy {
+            min_delay_ms: 0,
+            max_attempts: 3,
+            initial_backoff_ms: 1,
+            max_backoff_ms: 1,
+            max_retry_after_ms: 1000,
+        }
+    }
+
+    fn get_test_card(localizer: &Localizer) -> Card {
+        let scraper = OpTcgScraper::new(localizer, get_test_fetcher());
+        scraper.fetch_all_cards("569001").unwrap().remove(0)
+    }
+
+    #[test]
+    fn download_card_image_retries_server_errors() {
+        let localizer = get_test_localizer();
+        let card = get_test_card(&localizer);
+        let fetcher = FlakyFetcher {
+            failures: 2,
+            calls: Default::default(),
+        };
+
+        let scraper = OpTcgScraper::new(&localizer, &fetcher).with_policy(get_test_policy());
+        let image = scraper.download_card_image(&card).unwrap();
+
+        assert_eq!(image.status, 200);
+        assert_eq!(*fetcher.calls.lock().unwrap(), 3);
+    }
+
+    #[test]
+    fn download_card_image_long_retry_after_returns_err() {
+        let localizer = get_test_localizer();
+        let card = get_test_card(&localizer);
+
+        let mut response = FetchResponse::new(429, "text/html", Vec::new());
+        response
+            .headers
+            .insert(String::from("retry-after"), String::from("86400"));
+        let mut fetcher = MemoryFetcher::new();
+        fetcher.insert(card.img_full_url.as_ref().unwrap(), &[], response);
+
+        let scraper = OpTcgScraper::new(&localizer, fetcher).with_policy(get_test_policy());
+        let err = scraper.download_card_image(&card).unwrap_err();
+
+        assert!(format!("{:#}", err).contains("asked to retry after"));
+        assert!(matches!(
+            VegaError::find(&err),
+            Some(VegaError::HttpStatus { status: 429, .. })
+        ));
+    }
+
+    #[test]
+    fn download_card_image_gives_up_after_max_attempts() {
+        let localizer = get_test_localizer();
+        let card = get_test_card(&localizer);
+        let fetcher = FlakyFetcher {
+            failures: 5,
+            calls: Default::default(),
+        };
+
+        let scraper = OpTcgScraper::new(&localizer, &fetcher).with_policy(get_test_policy());
+
+        assert!(scraper.download_card_image(&card).is_err());
+        assert_eq!(*fetcher.calls.lock().unwrap(), 3);
+    }
+
+    #[test]
+    fn fetch_all_cards_does_not_retry_missing_fixture() {
+        let localizer = get_test_localizer();
+        let scraper =
+            OpTcgScraper::new(&localizer, get_test_fetcher()).with_policy(get_test_policy());
+
+        let err = scraper.fetch_all_cards("000000").unwrap_err();
+        assert!(!format!("{:#}", err).contains("giving up"));
+    }
+
+    #[test]
+    fn download_card_image_not_found_returns_err() {
+        let localizer = get_test_localizer();
+        let card = get_test_card(&localizer);
+
+        let mut fetcher = MemoryFetcher::new();
+        let html = b"<html>Not Found</html>".to_vec();
+        fetcher.insert(
+            card.img_full_url.as_ref().unwrap(),
+            &[],
+            FetchResponse::new(404, "text/html", html),
+        );
+
+        let scraper = OpTcgScraper::new(&localizer, fetcher).with_policy(get_test_policy());
+        let err = scraper.download_card_image(&card).unwrap_err();
+        assert!(err.to_string().contains("HTTP 404"));
+        assert!(matches!(
+            VegaError::find(&err),
+            Some(VegaError::HttpStatus { status: 404, .. })
+        ));
+    }
+
+    /// Pack 569101 where OP01-016 has a rarity missing from the locale.
+    fn get_unknown_rarity_fetcher(localizer: &Localizer) -> MemoryFetcher {
+        let html = fs::read_to_string(Path::new(FIXTURES_DIR).join("cardlist__series=569101.html"))
+            .unwrap()
+            .replace("<span>R</span>", "<span>XR</span>");
+
+        let mut fetcher = MemoryFetcher::new();
+        fetcher.insert_html(
+            &format!("{}/cardlist", localizer.hostname),
+            &[("series", "569101")],
+            &html,
+        );
+
+        fetcher
+    }
+
+    #[test]
+    fn fetch_all_cards_unknown_illustration_returns_other() {
+        let localizer = get_test_localizer();
+        let html = fs::read_to_string(Path::new(FIXTURES_DIR).join("cardlist__series=569101.html"))
+            .unwrap()
+            .replace("Illustration</h3>Original", "Illustration</h3>Parallel Art");
+
+        let mut fetcher = MemoryFetcher::new();
+        fetcher.insert_html(
+            &format!("{}/cardlist", localizer.hostname),
+            &[("series", "569101")],
+            &html,
+        );
+
+        let scraper = OpTcgScraper::new(&localizer, fetcher);
+        let cards = scraper.fetch_all_cards("569101").unwrap();
+
+        assert_eq!(cards.len(), 3);
+        assert_eq!(cards[0].illustration, Some(CardIllustration::Other));
+    }
+
+    /// Pack 569101 with two extra links, one without `data-src` and one with an empty one.
+    fn get_cards_fetcher(localizer: &Localizer, from: &str, to: &str) -> MemoryFetcher {
+        let html = fs::read_to_string(Path::new(FIXTURES_DIR).join("cardlist__series=569101.html"))
+            .unwrap()
+            .replace(from, to);
+
+        let mut fetcher = MemoryFetcher::new();
+        fetcher.insert_html(
+            &format!("{}/cardlist", localizer.hostname),
+            &[("series", "569101")],
+            &html,
+        );
+
+        fetcher
+    }
+
+    fn get_broken_links_fetcher(localizer: &Localizer) -> MemoryFetcher {
+        get_cards_fetcher(
+            localizer,
+            r#"<div class="resultCol">"#,
+            r#"<div class="resultCol"><a class="modalOpen"></a><a class="modalOpen" data-src=""></a>"#,
+        )
+    }
+
+    #[test]
+    fn fetch_all_cards_link_without_id_returns_err() {
+        let localizer = get_test_localizer();
+        let scraper = OpTcgScraper::new(&localizer, get_broken_links_fetcher(&localizer));
+        let err = scraper.fetch_all_cards("569101").unwrap_err();
+
+        assert!(matches!(
+            VegaError::find(&err),
+            Some(VegaError::Selector { .. })
+        ));
+    }
+
+    #[test]
+    fn fetch_all_cards_without_optional_node_returns_none() {
+        let localizer = get_test_localizer();
+        let fetcher = get_cards_fetcher(
+            &localizer,
+            r#"<div class="block"><h3>Block<br class="sp">icon</h3>1</div>"#,
+            "",
+        );
+        let scraper = OpTcgScraper::new(&localizer, fetcher);
+
+        let cards = scraper.fetch_all_cards("569101").unwrap();
+        assert!(cards.iter().all(|card| card.block.is_none()));
+    }
+
+    #[test]
+    fn fetch_all_cards_duplicate_optional_node_returns_err() {
+        let localizer = get_test_localizer();
+        let block = r#"<div class="block"><h3>Block<br class="sp">icon</h3>1</div>"#;
+        let fetcher = get_cards_fetcher(&localizer, block, &block.repeat(2));
+        let scraper = OpTcgScraper::new(&localizer, fetcher);
+        let err = scraper.fetch_all_cards("569101").unwrap_err();
+
+        assert!(matches!(
+            VegaError::find(&err),
+            Some(VegaError::Selector { selector, .. }) if *selector == localizer.selectors.block
+        ));
+    }
+
+    #[test]
+    fn fetch_all_cards_lenient_skips_links_without_id() {
+        let localizer = get_test_localizer();
+        let scraper =
+            OpTcgScraper::new(&localizer, get_broken_links_fetcher(&localizer)).with_lenient(true);
+
+        let cards = scraper.fetch_all_cards("569101").unwrap();
+        assert_eq!(cards.len(), 3);
+
+        let failures = scraper.take_failures();
+        assert_eq!(failures.len(), 2);
+        assert!(failures.iter().all(|failure| failure.card_id.is_empty()));
+        assert!(failures[0].html.starts_with("<a"));
+        assert!(failures[1].html.contains(r#"data-src="""#));
+    }
+
+    #[test]
+    fn fetch_all_cards_unknown_rarity_returns_localization_err() {
+        let localizer = get_test_localizer();
+        let scraper = OpTcgScraper::new(&localizer, get_unknown_rarity_fetcher(&localizer));
+        let err = scraper.fetch_all_cards("569101").unwrap_err();
+
+        assert!(matches!(
+            VegaError::find(&err),
+            Some(VegaError::Localization { section: "rarities", value }) if value == "XR"
+        ));
+        assert_eq!(
+            err.downcast_ref::<ScrapeLocation>(),
+            Some(&ScrapeLocation::card("569101", "OP01-016"))
+        );
+    }
+
+    #[test]
+    fn fetch_all_cards_lenient_skips_bad_cards() {
+        let localizer = get_test_localizer();
+        let scraper = OpTcgScraper::new(&localizer, get_unknown_rarity_fetcher(&localizer))
+            .with_lenient(true);
+
+        let cards = scraper.fetch_all_cards("569101").unwrap();
+        let ids: Vec<_> = cards.iter().map(|card| card.id.as_str()).collect();
+        assert_eq!(ids, vec!["OP01-001", "OP01-029"]);
+
+        let failures = scraper.take_failures();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].card_id, "OP01-016");
+        assert_eq!(failures[0].pack_id, "569101");
+        assert!(failures[0].reason.contains("`XR`"));
+        assert!(failures[0].html.starts_with("<dl"));
+        assert!(failures[0].html.contains("<span>XR</span>"));
+
+        assert!(scraper.take_failures().is_empty());
+    }
+
+    #[test]
+    fn fetch_all_cards_uses_configured_selectors() {
+        let mut localizer = get_test_localizer();
+        let html = fs::read_to_string(Path::new(FIXTURES_DIR).join("cardlist__series=569101.html"))
+            .unwrap()
+            .replace("cardName", "cardTitle");
+
+        let mut fetcher = MemoryFetcher::new();
+        fetcher.insert_html(
+            &format!("{}/cardlist", localizer.hostname),
+            &[("series", "569101")],
+            &html,
+        );
+
+        localizer.selectors.name = String::from("dt>div.cardTitle");
+        let scraper = OpTcgScraper::new(&localizer, fetcher);
+
+        let cards = scraper.fetch_all_cards("569101").unwrap();
+        assert_eq!(cards.len(), 3);
+        assert_eq!(cards[1].name, "Nami");
+    }
+
+    #[test]
+    fn fetch_all_products_matches_packs() {
+        let localizer = get_test_localizer();
+        let scraper = OpTcgScraper::new(&localizer, get_test_fetcher());
+
+        let packs = scraper.fetch_all_packs().unwrap();
+        let products = scraper.fetch_all_products(&packs).unwrap();
+        let ids: Vec<_> = products.iter().map(|p| p.pack_id.as_str()).collect();
+        assert_eq!(ids, vec!["569101", "569001"]);
+
+        let booster = &products[0];
+        assert_eq!(booster.release_date.unwrap().to_string(), "2022-12-02");
+        assert_eq!(booster.price.as_deref(), Some("$3.99"));
+        assert_eq!(
+            booster.img_full_url.as_deref(),
+            Some(
+                "https://en.onepiece-cardgame.com/images/products/boosters/op01/img_thumbnail.png"
+            )
+        );
+    }
+
+    #[test]
+    fn fetch_all_products_without_page_returns_err() {
+        let localizer = get_test_localizer();
+        let scraper =
+            OpTcgScraper::new(&localizer, MemoryFetcher::new()).with_policy(get_test_policy());
+
+        assert!(scraper.fetch_all_products(&[]).is_err());
+    }
+
+    #[test]
+    fn download_pack_image_returns_fixture_bytes() {
+        let localizer = get_test_localizer();
+        let scraper = OpTcgScraper::new(&localizer, get_test_fetcher());
+
+        let packs = scraper.fetch_all_packs().unwrap();
+        let products = scraper.fetch_all_products(&packs).unwrap();
+        let image = scraper.download_pack_image(&products[1]).unwrap();
+
+        assert_eq!(image.content_type(), Some("image/png"));
+        assert!(image.body.starts_with(b"\x89PNG"));
+    }
+
+    #[test]
+    fn download_pack_image_without_image_returns_err() {
+        let localizer = get_test_localizer();
+        let scraper = OpTcgScraper::new(&localizer, MemoryFetcher::new());
+
+        let product = ProductInfo {
+            pack_id: String::from("569101"),
+            title: String::from("BOOSTER PACK -ROMANCE DAWN- [OP-01]"),
+            release_date: None,
+            img_url: None,
+            img_full_url: None,
+            price: None,
+            pack_size: None,
+        };
+        assert!(scraper.download_pack_image(&product).is_err());
+    }
+
+    #[test]
+    fn download_card_image_not_an_image_returns_err() {
+        let localizer = get_test_localizer();
+        let card = get_test_card(&localizer);
+
+        let mut fetcher = MemoryFetcher::new();
+        fetcher.insert_html(card.img_full_url.as_ref().unwrap(), &[], "<html></html>");
+
+        let scraper = OpTcgScraper::new(&localizer, fetcher).with_policy(get_test_policy());
+        assert!(scraper.download_card_image(&card).is_err());
+    }
+
+    #[test]
+    fn download_card_image_returns_fixture_bytes() {
+        let localizer = get_test_localizer();
+        let scraper = OpTcgScraper::new(&localizer, get_test_fetcher());
+
+        let cards = scraper.fetch_all_cards("569001").unwrap();
+        let image = scraper.download_card_image(&cards[0]).unwrap();
+
+        assert_eq!(image.content_type(), Some("image/png"));
+        assert!(image.body.starts_with(b"\x89PNG"));
+    }
+}
@@ -0,0 +1,184 @@
+use anyhow::{bail, ensure, Result};
+use scraper::Selector;
+use serde::{Deserialize, Serialize};
+
+/// Id substituted in the `card` selector when validating it.
+const SAMPLE_CARD_ID: &str = "OP01-001";
+
+/// CSS selectors used to scrape the cardlist and products pages, set under `[selectors]` in
+/// locale files.
+///
+/// Card fields are relative to the `<dl>` node of the card.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Selectors {
+    /// Options of the series dropdown, one per pack
+    pub packs: String,
+    /// Links to the cards of the listed pack, pointing to their `<dl>` node
+    pub card_links: String,
+    /// `<dl>` node of a card, `{id}` is replaced by the card id
+    pub card: String,
+
+    pub name: String,
+    pub rarity: String,
+    pub category: String,
+    pub img_url: String,
+    pub colors: String,
+    pub cost: String,
+    pub attributes: String,
+    pub power: String,
+    pub counter: String,
+    pub types: String,
+    pub effect: String,
+    pub trigger: String,
+    pub illustration: String,
+    pub illustrator_name: String,
+    pub block: String,
+    pub product: String,
+
+    /// Entries of the products page, fields below are relative to them
+    pub products: String,
+    pub product_title: String,
+    pub product_release_date: String,
+    pub product_img_url: String,
+    pub product_price: String,
+    pub product_pack_size: String,
+}
+
+/// Fallback for keys a locale does not set, the same values are listed in `config/en.toml`.
+impl Default for Selectors {
+    fn default() -> Self {
+        Selectors {
+            packs: String::from("div.seriesCol>select#series>option"),
+            card_links: String::from("div.resultCol>a"),
+            card: String::from("dl#{id}"),
+            name: String::from("dt>div.cardName"),
+            rarity: String::from("dt>div.infoCol>span:nth-child(2)"),
+            category: String::from("dt>div.infoCol>span:nth-child(3)"),
+            img_url: String::from("dd>div.frontCol>img"),
+            colors: String::from("dd>div.backCol div.color"),
+            cost: String::from("dd>div.backCol>div.col2>div.cost"),
+            attributes: String::from("dd>div.backCol>div.col2>div.attribute>img"),
+            power: String::from("dd>div.backCol>div.col2>div.power"),
+            counter: String::from("dd>div.backCol>div.col2>div.counter"),
+            types: String::from("dd>div.backCol>div.feature"),
+            effect: String::from("dd>div.backCol>div.text"),
+            trigger: String::from("dd>div.backCol>div.trigger"),
+            illustration: String::from("dd>div.backCol>div.illustration"),
+            illustrator_name: String::from("dd>div.backCol>div.illustrator"),
+            block: String::from("dd>div.backCol>div.col2>div.block"),
+            product: String::from("dd>div.backCol>div.getInfo"),
+            products: String::from("div.productsList>div.productsCol"),
+            product_title: String::from(".productsTtl"),
+            product_release_date: String::from(".productsDate"),
+            product_img_url: String::from("img"),
+            product_price: String::from(".productsPrice"),
+            product_pack_size: String::from(".productsSize"),
+        }
+    }
+}
+
+impl Selectors {
+    /// Every selector along with its key in the `[selectors]` section.
+    pub fn entries(&self) -> [(&'static str, &str); 25] {
+        [
+            ("packs", &self.packs),
+            ("card_links", &self.card_links),
+            ("card", &self.card),
+            ("name", &self.name),
+            ("rarity", &self.rarity),
+            ("category", &self.category),
+            ("img_url", &self.img_url),
+            ("colors", &self.colors),
+            ("cost", &self.cost),
+            ("attributes", &self.attributes),
+            ("power", &self.power),
+            ("counter", &self.counter),
+            ("types", &self.types),
+            ("effect", &self.effect),
+            ("trigger", &self.trigger),
+            ("illustration", &self.illustration),
+            ("illustrator_name", &self.illustrator_name),
+            ("block", &self.block),
+            ("product", &self.product),
+            ("products", &self.products),
+            ("product_title", &self.product_title),
+            ("product_release_date", &self.product_release_date),
+            ("product_img_url", &self.product_img_url),
+            ("product_price", &self.product_price),
+            ("product_pack_size", &self.product_pack_size),
+        ]
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries()
+            .into_iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, sel)| sel)
+    }
+
+    /// Selector of the `<dl>` node of `card_id`.
+    pub fn card_node(&self, card_id: &str) -> String {
+        self.card.replace("{id}", card_id)
+    }
+
+    /// Makes sure every selector can be parsed, so a typo in a locale file is reported upfront
+    /// rather than in the middle of a pull.
+    pub fn validate(&self) -> Result<()> {
+        ensure!(
+            self.card.contains("{id}"),
+            "[selectors] `card` selector `{}` does not contain `{{id}}`",
+            self.card
+        );
+
+        for (key, sel) in self.entries() {
+            let sel = match key {
+                "card" => self.card_node(SAMPLE_CARD_ID),
+                _ => sel.to_string(),
+            };
+
+            if let Err(e) = Selector::parse(&sel).map_err(|e| e.to_string()) {
+                bail!("[selectors] invalid `{}` selector `{}`: {}", key, sel, e);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_defaults_returns_ok() {
+        assert!(Selectors::default().validate().is_ok());
+    }
+
+    #[test]
+    fn validate_invalid_selector_returns_err() {
+        let selectors = Selectors {
+            power: String::from("dd>>div.power["),
+            ..Selectors::default()
+        };
+
+        let err = selectors.validate().unwrap_err();
+        assert!(err.to_string().contains("`power`"), "{}", err);
+    }
+
+    #[test]
+    fn validate_card_without_id_returns_err() {
+        let selectors = Selectors {
+            card: String::from("dl.card"),
+            ..Selectors::default()
+        };
+
+        assert!(selectors.validate().is_err());
+    }
+
+    #[test]
+    fn card_node_replaces_id() {
+        let selectors = Selectors::default();
+        assert_eq!(selectors.card_node("OP01-001"), "dl#OP01-001");
+    }
+}
@@ -1,12 +1,14 @@
-use anyhow::{bail, Context, Result};
+use anyhow::{bail, ensure, Context, Result};
 use log::{debug, info, trace};
-use reqwest::blocking::Response;
 use std::{
     fs,
     path::{Path, PathBuf},
 };
 
-use crate::{card::Card, cli::LanguageCode, pack::Pack};
+use crate::{
+    card::Card, cli::LanguageCode, error::VegaError, fetcher::FetchResponse, manifest::Manifest,
+    pack::Pack, product::ProductInfo, scraper::CardFailure,
+};
 
 pub struct DataStore {
     root_dir: PathBuf,
@@ -17,10 +19,15 @@ pub enum StoreLocation<'a> {
     RootDir,
     LocaleDir,
     ImagesDir,
+    PackImagesDir,
     JsonDir,
     PacksListFile,
+    ProductsFile,
+    ManifestFile,
+    FailuresFile,
     CardsFile(&'a str),
     ImageFile(&'a Card),
+    PackImageFile(&'a ProductInfo),
 }
 
 impl DataStore {
@@ -38,15 +45,29 @@ impl DataStore {
                 .get_path(StoreLocation::RootDir)?
                 .join(self.locale.to_path()),
             StoreLocation::ImagesDir => self.get_path(StoreLocation::LocaleDir)?.join("images/"),
+            StoreLocation::PackImagesDir => self.get_path(StoreLocation::ImagesDir)?.join("packs/"),
             StoreLocation::JsonDir => self.get_path(StoreLocation::LocaleDir)?.join("json/"),
             StoreLocation::PacksListFile => {
                 self.get_path(StoreLocation::JsonDir)?.join("packs.json")
             }
+            StoreLocation::ProductsFile => {
+                self.get_path(StoreLocation::JsonDir)?.join("products.json")
+            }
+            StoreLocation::ManifestFile => {
+                self.get_path(StoreLocation::RootDir)?.join("manifest.json")
+            }
+            StoreLocation::FailuresFile => {
+                self.get_path(StoreLocation::JsonDir)?.join("failures.json")
+            }
             StoreLocation::CardsFile(pack_id) => self.get_cards_filename(pack_id)?,
             StoreLocation::ImageFile(card) => {
                 let filename = Self::get_img_filename(card)?;
                 self.get_path(StoreLocation::ImagesDir)?.join(filename)
             }
+            StoreLocation::PackImageFile(product) => {
+                let filename = Self::get_pack_img_filename(product)?;
+                self.get_path(StoreLocation::PackImagesDir)?.join(filename)
+            }
         };
 
         Ok(path.to_path_buf())
@@ -71,6 +92,26 @@ impl DataStore {
         Ok(img_file_name.to_string())
     }
 
+    /// Names the box art after the pack, e.g. `569101.png`, keeping the extension of its url.
+    pub fn get_pack_img_filename(product: &ProductInfo) -> Result<String> {
+        let img_url = product
+            .img_url
+            .as_deref()
+            .with_context(|| format!("no image for the product of pack `{}`", product.pack_id))?;
+
+        let img_url = img_url.split(['?', '#']).next().unwrap_or_default();
+        let extension = Path::new(img_url)
+            .extension()
+            .with_context(|| format!("expected an extension in `{}`", img_url))?;
+
+        let img_file_name = format!("{}.{}", product.pack_id, extension.to_string_lossy());
+        debug!(
+            "filename for pack `{}` is: {}",
+            product.pack_id, img_file_name
+        );
+        Ok(img_file_name)
+    }
+
     fn ensure_created(&self, location: StoreLocation) -> Result<()> {
         let root_dir = self.get_path(location)?;
         if root_dir.exists() {
@@ -80,7 +121,7 @@ impl DataStore {
 
         match fs::create_dir_all(&root_dir) {
             Ok(_) => info!("successfully created `{}`", root_dir.display()),
-            Err(e) => bail!("failed to create `{}`: {}", root_dir.display(), e),
+            Err(e) => bail!(VegaError::storage(root_dir, e)),
         }
 
         Ok(())
@@ -99,12 +140,31 @@ impl DataStore {
         let json = serde_json::to_string(&packs)?;
         trace!("serialize data: `{:?} -> {}`", packs, json);
 
-        fs::write(path, json)?;
+        fs::write(&path, json).map_err(|e| VegaError::storage(path, e))?;
         debug!("wrote packs data to file");
 
         Ok(())
     }
 
+    pub fn write_products(&self, products: &[ProductInfo]) -> Result<()> {
+        self.ensure_created(StoreLocation::JsonDir)?;
+
+        let path = self.get_path(StoreLocation::ProductsFile)?;
+        debug!(
+            "about to write {} products to file: `{}`",
+            products.len(),
+            path.display()
+        );
+
+        let json = serde_json::to_string(products)?;
+        trace!("serialize data: `{:?} -> {}`", products, json);
+
+        fs::write(&path, json).map_err(|e| VegaError::storage(path, e))?;
+        debug!("wrote products data to file");
+
+        Ok(())
+    }
+
     pub fn write_cards(&self, pack_id: &str, cards: &Vec<Card>) -> Result<()> {
         self.ensure_created(StoreLocation::JsonDir)?;
 
@@ -119,44 +179,289 @@ impl DataStore {
         let json = serde_json::to_string(&cards)?;
         trace!("serialize data: `{:?} -> {}`", cards, json);
 
-        fs::write(path, json)?;
+        fs::write(&path, json).map_err(|e| VegaError::storage(path, e))?;
         debug!("wrote cards data to file");
 
         Ok(())
     }
 
-    pub fn write_image_to_file(mut img_data: Response, path: &PathBuf) -> Result<()> {
-        debug!("about to save image to file: `{}`", path.display());
+    /// Records the cards skipped by a lenient pull, replacing the ones of the previous pull.
+    pub fn write_failures(&self, failures: &[CardFailure]) -> Result<()> {
+        self.ensure_created(StoreLocation::JsonDir)?;
+
+        let path = self.get_path(StoreLocation::FailuresFile)?;
+        debug!(
+            "about to write {} failures to file: `{}`",
+            failures.len(),
+            path.display()
+        );
+
+        let json = serde_json::to_string_pretty(failures)?;
+        fs::write(&path, json).map_err(|e| VegaError::storage(path, e))?;
+        debug!("wrote failures to file");
+
+        Ok(())
+    }
+
+    pub fn load_packs(&self) -> Result<Vec<Pack>> {
+        let path = self.get_path(StoreLocation::PacksListFile)?;
+        info!("load packs from: {}", path.display());
+
+        let json = fs::read_to_string(&path).map_err(|e| VegaError::storage(&path, e))?;
+
+        let packs: Vec<Pack> =
+            serde_json::from_str(&json).map_err(|e| Self::parse_error("packs", &path, e))?;
+        trace!("deserialize data: `{} -> {:?}`", json, packs);
+        debug!("loaded {} packs", packs.len());
+
+        Ok(packs)
+    }
+
+    /// Products of the last pull, none for data pulled before products were scraped.
+    pub fn load_products(&self) -> Result<Vec<ProductInfo>> {
+        let path = self.get_path(StoreLocation::ProductsFile)?;
+        if !path.exists() {
+            debug!("no products found at `{}`", path.display());
+            return Ok(Vec::new());
+        }
+
+        info!("load products from: {}", path.display());
+
+        let json = fs::read_to_string(&path).map_err(|e| VegaError::storage(&path, e))?;
+
+        let products: Vec<ProductInfo> =
+            serde_json::from_str(&json).map_err(|e| Self::parse_error("products", &path, e))?;
+        trace!("deserialize data: `{} -> {:?}`", json, products);
+        debug!("loaded {} products", products.len());
+
+        Ok(products)
+    }
+
+    pub fn load_cards(&self, pack_id: &str) -> Result<Vec<Card>> {
+        let path = self.get_path(StoreLocation::CardsFile(pack_id))?;
+        debug!("load cards from: {}", path.display());
+
+        let json = fs::read_to_string(&path).map_err(|e| VegaError::storage(&path, e))?;
+
+        let mut cards: Vec<Card> =
+            serde_json::from_str(&json).map_err(|e| Self::parse_error("cards", &path, e))?;
+        trace!("deserialize data: `{} -> {:?}`", json, cards);
+
+        for card in cards.iter_mut() {
+            card.normalize()?;
+        }
+        debug!("loaded {} cards for pack `{}`", cards.len(), pack_id);
+
+        Ok(cards)
+    }
+
+    pub fn load_manifest(&self) -> Result<Manifest> {
+        let path = self.get_path(StoreLocation::ManifestFile)?;
+        if !path.exists() {
+            debug!("no manifest found at `{}`", path.display());
+            return Ok(Manifest::default());
+        }
+
+        info!("load manifest from: {}", path.display());
+        let json = fs::read_to_string(&path).map_err(|e| VegaError::storage(&path, e))?;
+
+        let manifest: Manifest =
+            serde_json::from_str(&json).map_err(|e| Self::parse_error("manifest", &path, e))?;
+        trace!("deserialize data: `{} -> {:?}`", json, manifest);
+
+        Ok(manifest)
+    }
+
+    pub fn write_manifest(&self, manifest: &Manifest) -> Result<()> {
+        self.ensure_created(StoreLocation::RootDir)?;
 
-        let mut file = std::fs::File::create(path)?;
+        let path = self.get_path(StoreLocation::ManifestFile)?;
+        debug!("about to write manifest to file: `{}`", path.display());
 
-        img_data.copy_to(&mut file)?;
+        let json = serde_json::to_string_pretty(manifest)?;
+        Self::write_atomically(&path, json.as_bytes())?;
+        debug!("wrote manifest to file");
+
+        Ok(())
+    }
+
+    fn parse_error(field: &str, path: &Path, error: serde_json::Error) -> VegaError {
+        VegaError::Parse {
+            field: field.to_string(),
+            value: path.display().to_string(),
+            reason: error.to_string(),
+        }
+    }
+
+    /// Writes to a temporary file next to `path` first so that a crash never leaves it half written.
+    fn write_atomically(path: &Path, data: &[u8]) -> Result<()> {
+        let mut tmp_name = path
+            .file_name()
+            .context("expected path to have a file name")?
+            .to_os_string();
+        tmp_name.push(".tmp");
+        let tmp_path = path.with_file_name(tmp_name);
+
+        fs::write(&tmp_path, data).map_err(|e| VegaError::storage(&tmp_path, e))?;
+        fs::rename(&tmp_path, path).map_err(|e| VegaError::storage(path, e))?;
+
+        Ok(())
+    }
+
+    /// Makes sure the data looks like an image of the type announced by the file extension.
+    pub fn check_image_data(data: &[u8], path: &Path) -> Result<()> {
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        let is_valid = match extension.as_str() {
+            "png" => data.starts_with(b"\x89PNG\r\n\x1a\n"),
+            "jpg" | "jpeg" => data.starts_with(b"\xff\xd8\xff"),
+            "gif" => data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a"),
+            "webp" => data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP",
+            _ => bail!(
+                "unsupported image extension `{}` for `{}`",
+                extension,
+                path.display()
+            ),
+        };
+
+        ensure!(
+            is_valid,
+            "image data does not match the `{}` format of `{}`",
+            extension,
+            path.display()
+        );
+
+        Ok(())
+    }
+
+    pub fn write_image_to_file(img_data: FetchResponse, path: &Path) -> Result<()> {
+        debug!("about to save image to file: `{}`", path.display());
+
+        Self::check_image_data(&img_data.body, path)?;
+        Self::write_atomically(path, &img_data.body)?;
         debug!("saved image to file");
 
         Ok(())
     }
 
-    pub fn write_image(&self, card: &Card, img_data: Response) -> Result<()> {
+    pub fn write_image(&self, card: &Card, img_data: FetchResponse) -> Result<()> {
         self.ensure_created(StoreLocation::ImagesDir)?;
 
         let path = self.get_path(StoreLocation::ImageFile(card))?;
         Self::write_image_to_file(img_data, &path)?;
         Ok(())
     }
+
+    pub fn write_pack_image(&self, product: &ProductInfo, img_data: FetchResponse) -> Result<()> {
+        self.ensure_created(StoreLocation::PackImagesDir)?;
+
+        let path = self.get_path(StoreLocation::PackImageFile(product))?;
+        Self::write_image_to_file(img_data, &path)?;
+        Ok(())
+    }
 }
 
-// pub fn load_data() -> Result<OnePieceTcgData> {
-//     let path = data_file_path();
-//     info!("load data from: {}", path.to_string_lossy());
-//
-//     let json = fs::read_to_string(path)?;
-//     let data: OnePieceTcgData = serde_json::from_str(&json)?;
-//     trace!("deserialize data: `{} -> {:?}`", json, data);
-//     debug!(
-//         "loaded {} card sets ({} cards)",
-//         data.card_sets.len(),
-//         data.total_cards()
-//     );
-//
-//     Ok(data)
-// }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PNG_DATA: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+
+    fn get_test_product(img_url: Option<&str>) -> ProductInfo {
+        ProductInfo {
+            pack_id: String::from("569101"),
+            title: String::from("BOOSTER PACK -ROMANCE DAWN- [OP-01]"),
+            release_date: None,
+            img_url: img_url.map(String::from),
+            img_full_url: None,
+            price: None,
+            pack_size: None,
+        }
+    }
+
+    #[test]
+    fn get_pack_img_filename_uses_pack_id() {
+        let product = get_test_product(Some("../images/products/op01/img_thumbnail.webp?v=2"));
+        assert_eq!(
+            DataStore::get_pack_img_filename(&product).unwrap(),
+            "569101.webp"
+        );
+    }
+
+    #[test]
+    fn get_pack_img_filename_without_image_returns_err() {
+        assert!(DataStore::get_pack_img_filename(&get_test_product(None)).is_err());
+        let product = get_test_product(Some("../images/products/op01/thumbnail"));
+        assert!(DataStore::get_pack_img_filename(&product).is_err());
+    }
+
+    #[test]
+    fn pack_image_file_is_under_images_packs() {
+        let store = DataStore::new(Path::new("data"), LanguageCode::English);
+        let product = get_test_product(Some("../images/products/op01/img_thumbnail.png"));
+
+        let path = store
+            .get_path(StoreLocation::PackImageFile(&product))
+            .unwrap();
+        assert!(path.ends_with("images/packs/569101.png"));
+    }
+
+    #[test]
+    fn check_image_data_png_returns_ok() {
+        let path = Path::new("OP01-001.png");
+        assert!(DataStore::check_image_data(PNG_DATA, path).is_ok());
+    }
+
+    #[test]
+    fn check_image_data_jpg_returns_ok() {
+        let path = Path::new("OP01-001.JPG");
+        assert!(DataStore::check_image_data(b"\xff\xd8\xff\xe0", path).is_ok());
+    }
+
+    #[test]
+    fn check_image_data_html_returns_err() {
+        let path = Path::new("OP01-001.png");
+        let html = b"<!DOCTYPE html><html>404 Not Found</html>";
+        assert!(DataStore::check_image_data(html, path).is_err());
+    }
+
+    #[test]
+    fn check_image_data_wrong_format_returns_err() {
+        let path = Path::new("OP01-001.jpg");
+        assert!(DataStore::check_image_data(PNG_DATA, path).is_err());
+    }
+
+    #[test]
+    fn check_image_data_unknown_extension_returns_err() {
+        let path = Path::new("OP01-001.txt");
+        assert!(DataStore::check_image_data(PNG_DATA, path).is_err());
+    }
+
+    #[test]
+    fn write_image_to_file_keeps_no_temp_file() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+        let path = dir.join("OP01-001.png");
+
+        let img_data = FetchResponse::new(200, "image/png", PNG_DATA.to_vec());
+        DataStore::write_image_to_file(img_data, &path).unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), PNG_DATA);
+        assert!(!dir.join("OP01-001.png.tmp").exists());
+    }
+
+    #[test]
+    fn write_image_to_file_invalid_data_writes_nothing() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+        let path = dir.join("OP01-001.png");
+
+        let img_data = FetchResponse::new(200, "image/png", b"<html></html>".to_vec());
+        assert!(DataStore::write_image_to_file(img_data, &path).is_err());
+
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,341 @@
+use anyhow::{bail, Result};
+use clap::ValueEnum;
+use log::info;
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+use yansi::Paint;
+
+use crate::{
+    card::{
+        effect::{CardKeyword, CardTiming},
+        CardAttribute, CardCategory, CardColor, CardIllustration, CardRarity,
+    },
+    cli::LanguageCode,
+    localizer::{LabelMap, Localizer},
+};
+
+/// Top-level keys a locale file may define.
+const LOCALE_SECTIONS: [&str; 9] = [
+    "hostname",
+    "colors",
+    "attributes",
+    "categories",
+    "rarities",
+    "illustrations",
+    "effects",
+    "request",
+    "selectors",
+];
+
+/// Effect keys that are not a keyword or a timing.
+const EFFECT_MARKER_KEYS: [&str; 2] = ["once_per_turn", "don"];
+
+#[derive(Debug)]
+pub struct LocaleReport {
+    pub locale: &'static str,
+    pub override_path: Option<PathBuf>,
+    pub problems: Vec<String>,
+}
+
+impl LocaleReport {
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Checks the locale of every language and prints a pass/fail report, failing if any locale
+/// or override file is broken.
+pub fn validate_locales(config_dir: &Path) -> Result<()> {
+    if config_dir.exists() {
+        println!("config directory: {}", config_dir.display());
+    } else {
+        println!("config directory: {} (not found)", config_dir.display());
+    }
+
+    let mut failures = 0;
+    for language in LanguageCode::value_variants() {
+        let report = check_locale(config_dir, *language);
+        let name = language
+            .to_possible_value()
+            .map(|value| value.get_name().to_string())
+            .unwrap_or_else(|| format!("{:?}", language));
+
+        let source = match report.override_path {
+            Some(ref path) => format!("built-in + {}", path.display()),
+            None => String::from("built-in"),
+        };
+
+        if report.is_ok() {
+            println!(
+                "{} ({}.toml): {} [{}]",
+                name,
+                report.locale,
+                "OK".green(),
+                source
+            );
+            continue;
+        }
+
+        failures += 1;
+        println!(
+            "{} ({}.toml): {} [{}]",
+            name,
+            report.locale,
+            "FAILED".red(),
+            source
+        );
+        for problem in report.problems.iter() {
+            println!("  - {}", problem);
+        }
+    }
+
+    for file_name in find_unknown_locale_files(config_dir)? {
+        failures += 1;
+        println!(
+            "{}: {} [not a known locale, ignored]",
+            file_name,
+            "FAILED".red()
+        );
+    }
+
+    if failures > 0 {
+        bail!("{} locale(s) failed validation", failures);
+    }
+
+    info!("all locales are valid");
+    Ok(())
+}
+
+pub fn check_locale(config_dir: &Path, language: LanguageCode) -> LocaleReport {
+    let locale = Localizer::locale_name(language);
+    let mut report = LocaleReport {
+        locale,
+        override_path: None,
+        problems: Vec::new(),
+    };
+
+    let (locale_table, override_path) = match Localizer::load_table(config_dir, locale) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            report.problems.push(format!("{:#}", e));
+            return report;
+        }
+    };
+    report.override_path = override_path;
+
+    for section in locale_table.keys() {
+        if !LOCALE_SECTIONS.contains(&section.as_str()) {
+            report
+                .problems
+                .push(format!("unknown section `{}`", section));
+        }
+    }
+
+    match Localizer::deserialize(locale_table) {
+        Ok(localizer) => check_localizer(&localizer, &mut report.problems),
+        Err(e) => report.problems.push(format!("invalid locale: {}", e)),
+    }
+
+    report
+}
+
+fn check_localizer(localizer: &Localizer, problems: &mut Vec<String>) {
+    let hostname = localizer.hostname.as_str();
+    if !hostname.starts_with("https://") && !hostname.starts_with("http://") {
+        problems.push(format!("hostname `{}` is not an http(s) URL", hostname));
+    }
+
+    check_map("colors", &localizer.colors, &CardColor::KEYS, problems);
+    check_map(
+        "attributes",
+        &localizer.attributes,
+        &CardAttribute::KEYS,
+        problems,
+    );
+    check_map(
+        "categories",
+        &localizer.categories,
+        &CardCategory::KEYS,
+        problems,
+    );
+    check_map("rarities", &localizer.rarities, &CardRarity::KEYS, problems);
+    check_map(
+        "illustrations",
+        &localizer.illustrations,
+        &CardIllustration::KEYS,
+        problems,
+    );
+
+    let effect_keys: Vec<&str> = CardKeyword::KEYS
+        .iter()
+        .chain(CardTiming::KEYS.iter())
+        .chain(EFFECT_MARKER_KEYS.iter())
+        .copied()
+        .collect();
+    check_map("effects", &localizer.effects, &effect_keys, problems);
+
+    if let Err(e) = localizer.selectors.validate() {
+        problems.push(format!("{:#}", e));
+    }
+}
+
+/// Reports keys without a mapping, keys `from_str` would reject and values shared by several
+/// keys, which would make reverse lookups ambiguous.
+fn check_map(section: &str, map: &LabelMap, keys: &[&str], problems: &mut Vec<String>) {
+    for key in keys {
+        if !map.keys().any(|k| k.to_lowercase() == *key) {
+            problems.push(format!("[{}] missing `{}`", section, key));
+        }
+    }
+
+    let mut unknown_keys: Vec<&str> = map
+        .keys()
+        .map(String::as_str)
+        .filter(|k| !keys.contains(&k.to_lowercase().as_str()))
+        .collect();
+    unknown_keys.sort();
+
+    for key in unknown_keys {
+        problems.push(format!("[{}] unknown key `{}`", section, key));
+    }
+
+    let mut keys_by_value: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for (key, labels) in map.iter() {
+        for label in labels.iter() {
+            let keys = keys_by_value.entry(label).or_default();
+            if !keys.contains(&key.as_str()) {
+                keys.push(key);
+            }
+        }
+    }
+
+    for (value, mut keys) in keys_by_value {
+        if keys.len() > 1 {
+            keys.sort();
+            problems.push(format!(
+                "[{}] `{}` is used by several keys: {}",
+                section,
+                value,
+                keys.join(", ")
+            ));
+        }
+    }
+}
+
+fn find_unknown_locale_files(config_dir: &Path) -> Result<Vec<String>> {
+    if !config_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut unknown_files = Vec::new();
+    for entry in fs::read_dir(config_dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("toml") {
+            continue;
+        }
+
+        let locale = path.file_stem().unwrap_or_default().to_string_lossy();
+        if !Localizer::builtin_locales().any(|name| name == locale) {
+            unknown_files.push(
+                path.file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into(),
+            );
+        }
+    }
+
+    unknown_files.sort();
+    Ok(unknown_files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::localizer::Labels;
+
+    #[test]
+    fn check_locale_builtin_returns_no_problems() {
+        for language in LanguageCode::value_variants() {
+            let report = check_locale(Path::new("missing-dir"), *language);
+            assert!(report.is_ok(), "{:?}: {:?}", language, report.problems);
+        }
+    }
+
+    #[test]
+    fn check_locale_repo_config_returns_no_problems() {
+        for language in LanguageCode::value_variants() {
+            let report = check_locale(Path::new("config"), *language);
+            assert!(report.is_ok(), "{:?}: {:?}", language, report.problems);
+            assert!(report.override_path.is_some());
+        }
+    }
+
+    #[test]
+    fn check_map_reports_missing_unknown_and_duplicates() {
+        let mut map = LabelMap::new();
+        map.insert(String::from("Red"), Labels::One(String::from("Red")));
+        map.insert(String::from("green"), Labels::One(String::from("Green")));
+        map.insert(
+            String::from("blue"),
+            Labels::Many(vec![String::from("Blue"), String::from("Green")]),
+        );
+        map.insert(String::from("pink"), Labels::One(String::from("Pink")));
+
+        let mut problems = Vec::new();
+        check_map(
+            "colors",
+            &map,
+            &["red", "green", "blue", "black"],
+            &mut problems,
+        );
+
+        assert_eq!(
+            problems,
+            vec![
+                "[colors] missing `black`",
+                "[colors] unknown key `pink`",
+                "[colors] `Green` is used by several keys: blue, green",
+            ]
+        );
+    }
+
+    #[test]
+    fn check_locale_broken_override_returns_problems() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+        fs::write(
+            dir.join("en.toml"),
+            "hostname = \"en.onepiece-cardgame.com\"\n\n[colors]\nred = \"Green\"\n\n[colours]\n",
+        )
+        .unwrap();
+
+        let report = check_locale(dir, LanguageCode::English);
+
+        assert!(!report.is_ok());
+        assert!(report
+            .problems
+            .contains(&String::from("unknown section `colours`")));
+        assert!(report.problems.contains(&String::from(
+            "[colors] `Green` is used by several keys: green, red"
+        )));
+        assert!(report.problems.iter().any(|p| p.starts_with("hostname")));
+    }
+
+    #[test]
+    fn validate_locales_unknown_file_returns_err() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path();
+        fs::write(dir.join("asia_en.toml"), "").unwrap();
+
+        assert_eq!(
+            find_unknown_locale_files(dir).unwrap(),
+            vec![String::from("asia_en.toml")]
+        );
+        assert!(validate_locales(dir).is_err());
+    }
+}